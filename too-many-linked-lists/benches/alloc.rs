@@ -0,0 +1,77 @@
+// Run with `cargo bench --bench alloc`. Compares each list on the global
+// allocator against the same list drawing its nodes from a `NodePool`.
+
+mod common;
+
+use std::hint::black_box;
+
+use self::common::{best_of, report};
+use too_many_linked_lists::pool::NodePool;
+use too_many_linked_lists::{second, sixth};
+
+const LEN: u64 = 1_000_000;
+const CHURN_ROUNDS: u64 = 1_000;
+const CHURN_LEN: u64 = 1_000;
+
+// "fill" builds one long list and tears it down again; "churn" does the
+// same over and over with short lists. The pool outlives each run, so after
+// the first one every node it hands out is recycled.
+macro_rules! bench_list {
+    ($name:expr, $new:expr, $push:ident, $pop:ident) => {{
+        report(
+            "fill",
+            $name,
+            best_of(
+                || (),
+                |()| {
+                    let mut list = $new;
+                    for i in 0..LEN {
+                        list.$push(black_box(i));
+                    }
+                    while black_box(list.$pop()).is_some() {}
+                },
+            ),
+        );
+
+        report(
+            "churn",
+            $name,
+            best_of(
+                || $new,
+                |mut list| {
+                    for _ in 0..CHURN_ROUNDS {
+                        for i in 0..CHURN_LEN {
+                            list.$push(black_box(i));
+                        }
+                        while black_box(list.$pop()).is_some() {}
+                    }
+                },
+            ),
+        );
+    }};
+}
+
+fn main() {
+    let pool = NodePool::new();
+    bench_list!("second::List", second::List::new(), push, pop);
+    bench_list!(
+        "second::List<_, &NodePool>",
+        second::List::new_in(&pool),
+        push,
+        pop
+    );
+
+    let pool = NodePool::new();
+    bench_list!(
+        "sixth::LinkedList",
+        sixth::LinkedList::new(),
+        push_back,
+        pop_front
+    );
+    bench_list!(
+        "sixth::LinkedList<_, &NodePool>",
+        sixth::LinkedList::new_in(&pool),
+        push_back,
+        pop_front
+    );
+}
@@ -0,0 +1,24 @@
+// Timing helpers shared by the benches.
+
+use std::time::{Duration, Instant};
+
+const RUNS: usize = 5;
+
+pub fn best_of<S, F>(mut setup: impl FnMut() -> S, mut run: F) -> Duration
+where
+    F: FnMut(S),
+{
+    (0..RUNS)
+        .map(|_| {
+            let state = setup();
+            let start = Instant::now();
+            run(state);
+            start.elapsed()
+        })
+        .min()
+        .unwrap()
+}
+
+pub fn report(group: &str, name: &str, time: Duration) {
+    println!("{:<10} {:<32} {:>10.2?}", group, name, time);
+}
@@ -0,0 +1,114 @@
+// Run with `cargo bench --bench unrolled`. Plain `Instant` timings, so no
+// extra dependencies are needed; each case reports the best of several runs.
+
+mod common;
+
+use std::collections::{LinkedList, VecDeque};
+use std::hint::black_box;
+
+use self::common::{best_of, report};
+use too_many_linked_lists::{second, unrolled};
+
+const LEN: u64 = 1_000_000;
+
+// Every list gets the same three workloads: build it one element at a time,
+// sum it by iterating, and tear it down one element at a time.
+macro_rules! bench_list {
+    ($name:expr, $new:expr, $push:ident, $pop:ident) => {{
+        report(
+            "push",
+            $name,
+            best_of(
+                || $new,
+                |mut list| {
+                    for i in 0..LEN {
+                        list.$push(black_box(i));
+                    }
+                    black_box(list);
+                },
+            ),
+        );
+
+        let mut full = $new;
+        for i in 0..LEN {
+            full.$push(i);
+        }
+        report(
+            "iter",
+            $name,
+            best_of(
+                || (),
+                |()| {
+                    black_box(full.iter().sum::<u64>());
+                },
+            ),
+        );
+
+        report(
+            "pop",
+            $name,
+            best_of(
+                || {
+                    let mut list = $new;
+                    for i in 0..LEN {
+                        list.$push(i);
+                    }
+                    list
+                },
+                |mut list| while black_box(list.$pop()).is_some() {},
+            ),
+        );
+    }};
+}
+
+fn main() {
+    bench_list!("second::List", second::List::new(), push, pop);
+    bench_list!("std LinkedList", LinkedList::new(), push_back, pop_front);
+    bench_list!("VecDeque", VecDeque::new(), push_back, pop_front);
+    bench_list!(
+        "unrolled::List<_, 16>",
+        unrolled::List::<_, 16>::new(),
+        push_back,
+        pop_front
+    );
+    bench_list!(
+        "unrolled::List<_, 64>",
+        unrolled::List::<_, 64>::new(),
+        push_back,
+        pop_front
+    );
+
+    // Inserting and removing in the middle. std's LinkedList has no stable
+    // way to do this, so it sits this one out.
+    const MIDDLE_OPS: u64 = 20_000;
+    const MIDDLE_LEN: u64 = 100_000;
+
+    report(
+        "middle",
+        "VecDeque",
+        best_of(
+            || (0..MIDDLE_LEN).collect::<VecDeque<_>>(),
+            |mut list| {
+                for i in 0..MIDDLE_OPS {
+                    let at = list.len() / 2;
+                    list.insert(at, i);
+                    black_box(list.remove(at / 2));
+                }
+            },
+        ),
+    );
+    report(
+        "middle",
+        "unrolled::List<_, 64>",
+        best_of(
+            || (0..MIDDLE_LEN).collect::<unrolled::List<_, 64>>(),
+            |mut list| {
+                for i in 0..MIDDLE_OPS {
+                    let at = list.len() / 2;
+                    list.insert(at, i);
+                    black_box(list.remove(at / 2));
+                }
+            },
+        ),
+    );
+}
@@ -0,0 +1,451 @@
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::third::List;
+
+const MAGIC: &[u8; 4] = b"TMLL";
+const VERSION: u8 = 1;
+
+// Writes any number of `third::List`s so that every node is stored once,
+// however many of the lists share it, and reads them back with the same
+// sharing.
+//
+// The format is the magic bytes and a version, then the node table, then the
+// lists. The table is a count followed by one record per node: a reference
+// to its tail and the element's bytes, prefixed with their length. The lists
+// are a count and one reference each. A reference is 0 for the empty list
+// and `i + 1` for the `i`th node, and every number is an unsigned LEB128
+// varint. The encoder always writes a node's tail before the node itself,
+// but the decoder doesn't rely on that, so it checks for cycles instead.
+
+// How elements are turned into bytes and back. `decode` gets exactly the
+// bytes `encode` wrote for an element, and returns `None` if they don't make
+// one.
+pub trait ElementCodec<T> {
+    fn encode(&self, elem: &T, out: &mut Vec<u8>);
+    fn decode(&self, bytes: &[u8]) -> Option<T>;
+}
+
+// Integers as their little-endian bytes.
+pub struct Le;
+
+// Strings as their UTF-8 bytes.
+pub struct Utf8;
+
+macro_rules! le_codec {
+    ($($int:ty),*) => {
+        $(
+            impl ElementCodec<$int> for Le {
+                fn encode(&self, elem: &$int, out: &mut Vec<u8>) {
+                    out.extend_from_slice(&elem.to_le_bytes());
+                }
+
+                fn decode(&self, bytes: &[u8]) -> Option<$int> {
+                    bytes.try_into().ok().map(<$int>::from_le_bytes)
+                }
+            }
+        )*
+    };
+}
+
+le_codec!(u8, u16, u32, u64, u128, i8, i16, i32, i64, i128);
+
+impl ElementCodec<String> for Utf8 {
+    fn encode(&self, elem: &String, out: &mut Vec<u8>) {
+        out.extend_from_slice(elem.as_bytes());
+    }
+
+    fn decode(&self, bytes: &[u8]) -> Option<String> {
+        String::from_utf8(bytes.to_vec()).ok()
+    }
+}
+
+// Any pair of an encoding and a decoding closure.
+impl<T, E, D> ElementCodec<T> for (E, D)
+where
+    E: Fn(&T, &mut Vec<u8>),
+    D: Fn(&[u8]) -> Option<T>,
+{
+    fn encode(&self, elem: &T, out: &mut Vec<u8>) {
+        (self.0)(elem, out)
+    }
+
+    fn decode(&self, bytes: &[u8]) -> Option<T> {
+        (self.1)(bytes)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DecodeError {
+    BadMagic,
+    UnsupportedVersion(u8),
+    UnexpectedEnd,
+    // A varint that doesn't fit in 64 bits.
+    Overflow,
+    // A reference to a node past the end of the table.
+    DanglingReference(u64),
+    // A node whose tail leads back to itself.
+    Cycle(usize),
+    // A node whose element bytes the codec rejected.
+    BadElement(usize),
+    // Bytes left over after the last list.
+    TrailingBytes(usize),
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::BadMagic => write!(f, "not an encoded list"),
+            DecodeError::UnsupportedVersion(v) => write!(f, "unsupported format version {}", v),
+            DecodeError::UnexpectedEnd => write!(f, "unexpected end of input"),
+            DecodeError::Overflow => write!(f, "number too large"),
+            DecodeError::DanglingReference(r) => write!(f, "reference {} to a missing node", r),
+            DecodeError::Cycle(node) => write!(f, "node {} is part of a cycle", node),
+            DecodeError::BadElement(node) => write!(f, "node {} has an invalid element", node),
+            DecodeError::TrailingBytes(n) => write!(f, "{} bytes after the end", n),
+        }
+    }
+}
+
+impl core::error::Error for DecodeError {}
+
+pub fn encode<T, C: ElementCodec<T>>(lists: &[&List<T>], codec: &C) -> Vec<u8> {
+    let mut ids = BTreeMap::new();
+    let mut nodes = Vec::new();
+    let mut roots = Vec::with_capacity(lists.len());
+
+    for list in lists {
+        // Every node down to the first one that's already been written
+        let mut fresh = Vec::new();
+        let mut cur = (*list).clone();
+        while !cur.is_empty() && !ids.contains_key(&cur.head_ptr()) {
+            fresh.push(cur.clone());
+            cur = cur.tail();
+        }
+
+        for node in fresh.iter().rev() {
+            let tail = reference(&ids, &node.tail());
+            let mut bytes = Vec::new();
+            codec.encode(node.head().unwrap(), &mut bytes);
+            nodes.push((tail, bytes));
+            ids.insert(node.head_ptr(), nodes.len() as u64);
+        }
+        roots.push(reference(&ids, list));
+    }
+
+    let mut out = Vec::new();
+    out.extend_from_slice(MAGIC);
+    out.push(VERSION);
+    write_varint(&mut out, nodes.len() as u64);
+    for (tail, bytes) in &nodes {
+        write_varint(&mut out, *tail);
+        write_varint(&mut out, bytes.len() as u64);
+        out.extend_from_slice(bytes);
+    }
+    write_varint(&mut out, roots.len() as u64);
+    for root in roots {
+        write_varint(&mut out, root);
+    }
+    out
+}
+
+pub fn decode<T, C: ElementCodec<T>>(bytes: &[u8], codec: &C) -> Result<Vec<List<T>>, DecodeError> {
+    let mut input = Reader { bytes };
+    if input.take(MAGIC.len())? != MAGIC {
+        return Err(DecodeError::BadMagic);
+    }
+    match input.take(1)?[0] {
+        VERSION => {}
+        version => return Err(DecodeError::UnsupportedVersion(version)),
+    }
+
+    let count = input.varint()?;
+    let mut tails = Vec::new();
+    let mut elems = Vec::new();
+    for node in 0..count {
+        let tail = input.varint()?;
+        let len = input.varint()?;
+        let len = usize::try_from(len).map_err(|_| DecodeError::UnexpectedEnd)?;
+        let elem = codec
+            .decode(input.take(len)?)
+            .ok_or(DecodeError::BadElement(node as usize))?;
+        tails.push(tail);
+        elems.push(Some(elem));
+    }
+
+    let node = |reference: u64| match reference {
+        0 => Ok(None),
+        r if r <= count => Ok(Some(r as usize - 1)),
+        r => Err(DecodeError::DanglingReference(r)),
+    };
+    let tails = tails.into_iter().map(node).collect::<Result<Vec<_>, _>>()?;
+
+    // Each node is built once, after its tail, by following tails down to
+    // something already built and prepending on the way back. Running into
+    // a node that's still waiting on its tail means we've gone in a circle.
+    let mut built: Vec<Option<List<T>>> = vec![None; tails.len()];
+    let mut pending = vec![false; tails.len()];
+    for start in 0..tails.len() {
+        let mut chain = Vec::new();
+        let mut cur = Some(start);
+        let mut list = loop {
+            match cur {
+                None => break List::new(),
+                Some(i) if built[i].is_some() => break built[i].clone().unwrap(),
+                Some(i) if pending[i] => return Err(DecodeError::Cycle(i)),
+                Some(i) => {
+                    pending[i] = true;
+                    chain.push(i);
+                    cur = tails[i];
+                }
+            }
+        };
+        for i in chain.into_iter().rev() {
+            list = list.prepend(elems[i].take().unwrap());
+            built[i] = Some(list.clone());
+        }
+    }
+
+    let roots = input.varint()?;
+    let mut lists = Vec::new();
+    for _ in 0..roots {
+        lists.push(match node(input.varint()?)? {
+            Some(i) => built[i].clone().unwrap(),
+            None => List::new(),
+        });
+    }
+
+    if !input.bytes.is_empty() {
+        return Err(DecodeError::TrailingBytes(input.bytes.len()));
+    }
+    Ok(lists)
+}
+
+fn reference<T>(ids: &BTreeMap<*const (), u64>, list: &List<T>) -> u64 {
+    if list.is_empty() {
+        0
+    } else {
+        ids[&list.head_ptr()]
+    }
+}
+
+fn write_varint(out: &mut Vec<u8>, mut n: u64) {
+    while n >= 0x80 {
+        out.push(n as u8 | 0x80);
+        n >>= 7;
+    }
+    out.push(n as u8);
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], DecodeError> {
+        if n > self.bytes.len() {
+            return Err(DecodeError::UnexpectedEnd);
+        }
+        let (taken, rest) = self.bytes.split_at(n);
+        self.bytes = rest;
+        Ok(taken)
+    }
+
+    fn varint(&mut self) -> Result<u64, DecodeError> {
+        let mut n = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = self.take(1)?[0];
+            let bits = (byte & 0x7f) as u64;
+            if bits << shift >> shift != bits {
+                return Err(DecodeError::Overflow);
+            }
+            n |= bits << shift;
+            if byte & 0x80 == 0 {
+                return Ok(n);
+            }
+        }
+        Err(DecodeError::Overflow)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode, encode, write_varint, DecodeError, Le, Utf8, MAGIC, VERSION};
+    use crate::third::List;
+
+    fn to_vec<T: Clone>(list: &List<T>) -> Vec<T> {
+        list.iter().cloned().collect()
+    }
+
+    // The header, then the given numbers as varints.
+    fn raw(numbers: &[u64]) -> Vec<u8> {
+        let mut out = MAGIC.to_vec();
+        out.push(VERSION);
+        for &n in numbers {
+            write_varint(&mut out, n);
+        }
+        out
+    }
+
+    #[test]
+    fn round_trip_keeps_sharing() {
+        let base: List<u32> = (0..100).collect();
+        let a = base.prepend(1000);
+        let b = base.prepend(2000).prepend(2001);
+        let c = b.tail();
+        let empty = List::new();
+
+        let bytes = encode(&[&a, &b, &base, &c, &empty, &a], &Le);
+        let lists = decode::<u32, _>(&bytes, &Le).unwrap();
+        assert_eq!(lists.len(), 6);
+        for (decoded, original) in lists.iter().zip([&a, &b, &base, &c, &empty, &a]) {
+            assert_eq!(decoded, original);
+        }
+
+        let [a, b, base, c, empty, a2] = &lists[..] else {
+            unreachable!()
+        };
+        assert!(a.tail().ptr_eq(base));
+        assert!(b.tail().tail().ptr_eq(base));
+        assert!(b.tail().ptr_eq(c));
+        assert!(a.ptr_eq(a2));
+        assert!(empty.is_empty());
+    }
+
+    #[test]
+    fn shared_nodes_are_written_once() {
+        let base: List<u64> = (0..1_000).collect();
+        let versions: Vec<_> = (0..10).map(|i| base.prepend(i)).collect();
+
+        let shared = encode(&versions.iter().collect::<Vec<_>>(), &Le);
+        let alone = encode(&[&versions[0]], &Le);
+        // Each extra version only costs its own node and its reference
+        assert!(
+            shared.len() < alone.len() + 9 * 16,
+            "{} bytes",
+            shared.len()
+        );
+    }
+
+    #[test]
+    fn custom_codecs() {
+        let words: List<String> = ["shared", "tail"].iter().map(|s| s.to_string()).collect();
+        let longer = words.prepend("a".to_string());
+        let bytes = encode(&[&words, &longer], &Utf8);
+        let lists = decode(&bytes, &Utf8).unwrap();
+        assert_eq!(to_vec(&lists[1]), vec!["a", "shared", "tail"]);
+        assert!(lists[1].tail().ptr_eq(&lists[0]));
+
+        // Booleans as a single byte, rejecting anything but 0 and 1
+        let codec = (
+            |b: &bool, out: &mut Vec<u8>| out.push(*b as u8),
+            |bytes: &[u8]| match bytes {
+                [0] => Some(false),
+                [1] => Some(true),
+                _ => None,
+            },
+        );
+        let list: List<_> = [true, false, true].into_iter().collect();
+        let bytes = encode(&[&list], &codec);
+        assert_eq!(decode(&bytes, &codec).unwrap(), vec![list]);
+
+        let bad = raw(&[1, 0, 1, 2, 1, 1]);
+        assert_eq!(decode(&bad, &codec), Err(DecodeError::BadElement(0)));
+    }
+
+    #[test]
+    fn rejects_malformed_input() {
+        let list: List<u16> = (0..10).collect();
+        let bytes = encode(&[&list, &list.tail()], &Le);
+
+        // Cutting it short anywhere is caught, not a panic
+        for len in 0..bytes.len() {
+            assert!(decode::<u16, _>(&bytes[..len], &Le).is_err());
+        }
+
+        let mut wrong = bytes.clone();
+        wrong[0] = b'X';
+        assert_eq!(decode::<u16, _>(&wrong, &Le), Err(DecodeError::BadMagic));
+        wrong = bytes.clone();
+        wrong[4] = 9;
+        assert_eq!(
+            decode::<u16, _>(&wrong, &Le),
+            Err(DecodeError::UnsupportedVersion(9))
+        );
+        wrong = bytes.clone();
+        wrong.extend([0, 0]);
+        assert_eq!(
+            decode::<u16, _>(&wrong, &Le),
+            Err(DecodeError::TrailingBytes(2))
+        );
+
+        // A u16 has to be exactly two bytes
+        let bad = raw(&[1, 0, 1, 7, 1, 1]);
+        assert_eq!(decode::<u16, _>(&bad, &Le), Err(DecodeError::BadElement(0)));
+
+        // A tail past the table, and a list past the table
+        let dangling = raw(&[1, 5, 1, 7, 1, 1]);
+        assert_eq!(
+            decode::<u8, _>(&dangling, &Le),
+            Err(DecodeError::DanglingReference(5))
+        );
+        let dangling = raw(&[1, 0, 1, 7, 1, 2]);
+        assert_eq!(
+            decode::<u8, _>(&dangling, &Le),
+            Err(DecodeError::DanglingReference(2))
+        );
+
+        let mut overflow = raw(&[]);
+        overflow.extend([0xff; 10]);
+        overflow.push(1);
+        assert_eq!(decode::<u8, _>(&overflow, &Le), Err(DecodeError::Overflow));
+    }
+
+    #[test]
+    fn rejects_cycles() {
+        // A node that is its own tail
+        let own_tail = raw(&[1, 1, 1, 7, 1, 1]);
+        assert_eq!(decode::<u8, _>(&own_tail, &Le), Err(DecodeError::Cycle(0)));
+
+        // Nodes 1 and 2 are each other's tails, and 0 and 3 lead into them
+        let cycle = raw(&[4, 3, 1, 0, 3, 1, 1, 2, 1, 2, 1, 1, 3, 1, 1]);
+        assert!(matches!(
+            decode::<u8, _>(&cycle, &Le),
+            Err(DecodeError::Cycle(1 | 2))
+        ));
+
+        // Forward references are fine as long as they end somewhere
+        let forward = raw(&[2, 2, 1, 7, 0, 1, 8, 1, 1]);
+        let lists = decode::<u8, _>(&forward, &Le).unwrap();
+        assert_eq!(to_vec(&lists[0]), vec![7, 8]);
+    }
+
+    #[test]
+    fn zero_sized_elements() {
+        let base: List<()> = (0..3).map(|_| ()).collect();
+        let lists = [base.prepend(()), base.prepend(()), base.clone()];
+        let codec = (|_: &(), _: &mut Vec<u8>| {}, |_: &[u8]| Some(()));
+
+        let decoded = decode(&encode(&[&lists[0], &lists[1], &lists[2]], &codec), &codec).unwrap();
+        assert_eq!(
+            decoded.iter().map(List::len).collect::<Vec<_>>(),
+            vec![4, 4, 3]
+        );
+        assert!(!decoded[0].ptr_eq(&decoded[1]));
+        assert!(decoded[0].tail().ptr_eq(&decoded[2]));
+        assert!(decoded[1].tail().ptr_eq(&decoded[2]));
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn long_lists() {
+        let list: List<u32> = (0..1_000_000).collect();
+        let bytes = encode(&[&list, &list.tail()], &Le);
+        let lists = decode::<u32, _>(&bytes, &Le).unwrap();
+        assert_eq!(lists[0], list);
+        assert!(lists[0].tail().ptr_eq(&lists[1]));
+    }
+}
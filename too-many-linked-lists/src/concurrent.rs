@@ -0,0 +1,23 @@
+mod deque;
+mod hazard;
+mod queue;
+mod stack;
+
+pub use self::deque::{Stealer, WorkStealingDeque, Worker};
+pub use self::queue::Queue;
+pub use self::stack::Stack;
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    // Counts its drops, so tests can check nothing leaks or is dropped twice.
+    pub(super) struct Counted(pub(super) Arc<AtomicUsize>);
+
+    impl Drop for Counted {
+        fn drop(&mut self) {
+            self.0.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
@@ -0,0 +1,457 @@
+use std::cell::{Cell, UnsafeCell};
+use std::marker::PhantomData;
+use std::mem::MaybeUninit;
+use std::ptr;
+use std::sync::atomic::{fence, AtomicIsize, AtomicPtr, Ordering};
+use std::sync::Arc;
+
+use super::hazard::Domain;
+
+const MIN_CAPACITY: usize = 16;
+
+// Chase and Lev's work-stealing deque, with the memory orderings from Lê,
+// Pop, Cohen and Zappa Nardelli's "Correct and Efficient Work-Stealing for
+// Weak Memory Models". The owning thread pushes and pops at the bottom
+// through its `Worker`, and any number of `Stealer`s take from the top. The
+// owner and the thieves only ever compete for the last element, and settle
+// it with a CAS on `top`.
+//
+// The buffer is a ring indexed by `top` and `bottom`, which only ever grow.
+// When it's full the owner copies it into one twice the size. Thieves may
+// still be reading the old one, so it's retired to a hazard pointer domain
+// instead of being freed right away.
+pub struct WorkStealingDeque<T> {
+    top: AtomicIsize,
+    bottom: AtomicIsize,
+    buffer: AtomicPtr<Buffer<T>>,
+    domain: Domain,
+    _boo: PhantomData<T>,
+}
+
+// Slots are never dropped by the buffer itself: elements are moved out by
+// whoever takes them, and moved into the new buffer when it grows.
+struct Buffer<T> {
+    slots: Box<[UnsafeCell<MaybeUninit<T>>]>,
+}
+
+impl<T> Buffer<T> {
+    fn alloc(capacity: usize) -> *mut Self {
+        let slots = (0..capacity)
+            .map(|_| UnsafeCell::new(MaybeUninit::uninit()))
+            .collect();
+        Box::into_raw(Box::new(Buffer { slots }))
+    }
+
+    fn capacity(&self) -> usize {
+        self.slots.len()
+    }
+
+    fn slot(&self, index: isize) -> *mut MaybeUninit<T> {
+        self.slots[index as usize & (self.capacity() - 1)].get()
+    }
+
+    // A thief can read a slot while the owner is overwriting it, but only
+    // when the thief's CAS is bound to fail and the value gets thrown away.
+    // Both sides go through volatile accesses of `MaybeUninit` so the torn
+    // value is never treated as a `T`.
+    unsafe fn write(&self, index: isize, elem: MaybeUninit<T>) {
+        unsafe { ptr::write_volatile(self.slot(index), elem) }
+    }
+
+    unsafe fn read(&self, index: isize) -> MaybeUninit<T> {
+        unsafe { ptr::read_volatile(self.slot(index)) }
+    }
+}
+
+// The owner's end. There's only ever one, and it can move between threads
+// but not be shared by them.
+pub struct Worker<T> {
+    deque: Arc<WorkStealingDeque<T>>,
+    _not_sync: PhantomData<Cell<()>>,
+}
+
+pub struct Stealer<T> {
+    deque: Arc<WorkStealingDeque<T>>,
+}
+
+impl<T> WorkStealingDeque<T> {
+    // Like a channel, the deque itself is only ever reached through its ends.
+    #[allow(clippy::new_ret_no_self)]
+    pub fn new() -> (Worker<T>, Stealer<T>) {
+        WorkStealingDeque::with_capacity(MIN_CAPACITY)
+    }
+
+    // The capacity is rounded up to a power of two.
+    pub fn with_capacity(capacity: usize) -> (Worker<T>, Stealer<T>) {
+        let capacity = capacity.max(MIN_CAPACITY).next_power_of_two();
+        let deque = Arc::new(WorkStealingDeque {
+            top: AtomicIsize::new(0),
+            bottom: AtomicIsize::new(0),
+            buffer: AtomicPtr::new(Buffer::alloc(capacity)),
+            domain: Domain::new(),
+            _boo: PhantomData,
+        });
+        let stealer = Stealer {
+            deque: deque.clone(),
+        };
+        let worker = Worker {
+            deque,
+            _not_sync: PhantomData,
+        };
+        (worker, stealer)
+    }
+
+    // Only a snapshot, like `Queue::len`.
+    fn len(&self) -> usize {
+        let bottom = self.bottom.load(Ordering::Relaxed);
+        let top = self.top.load(Ordering::Relaxed);
+        (bottom - top).max(0) as usize
+    }
+}
+
+impl<T> Worker<T> {
+    pub fn stealer(&self) -> Stealer<T> {
+        Stealer {
+            deque: self.deque.clone(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.deque.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn capacity(&self) -> usize {
+        unsafe { (*self.deque.buffer.load(Ordering::Relaxed)).capacity() }
+    }
+
+    pub fn push(&self, elem: T) {
+        let deque = &*self.deque;
+        let bottom = deque.bottom.load(Ordering::Relaxed);
+        let top = deque.top.load(Ordering::Acquire);
+        let mut buffer = deque.buffer.load(Ordering::Relaxed);
+
+        if bottom - top >= unsafe { (*buffer).capacity() } as isize {
+            buffer = self.grow(buffer, top, bottom);
+        }
+
+        unsafe { (*buffer).write(bottom, MaybeUninit::new(elem)) };
+        fence(Ordering::Release);
+        deque.bottom.store(bottom + 1, Ordering::Relaxed);
+    }
+
+    // Newest first, so the owner works through its own tasks depth-first
+    // while thieves take the oldest, usually biggest, ones.
+    pub fn pop(&self) -> Option<T> {
+        let deque = &*self.deque;
+        let bottom = deque.bottom.load(Ordering::Relaxed) - 1;
+        let buffer = deque.buffer.load(Ordering::Relaxed);
+
+        // Claim the bottom slot before looking at `top`, so a thief that
+        // goes for the same element afterwards sees it's gone.
+        deque.bottom.store(bottom, Ordering::Relaxed);
+        fence(Ordering::SeqCst);
+        let top = deque.top.load(Ordering::Relaxed);
+
+        if top > bottom {
+            deque.bottom.store(bottom + 1, Ordering::Relaxed);
+            return None;
+        }
+
+        let elem = unsafe { (*buffer).read(bottom) };
+        if top == bottom {
+            // The last element, which a thief may be after too
+            let won = deque
+                .top
+                .compare_exchange(top, top + 1, Ordering::SeqCst, Ordering::Relaxed)
+                .is_ok();
+            deque.bottom.store(bottom + 1, Ordering::Relaxed);
+            if !won {
+                return None;
+            }
+        }
+        Some(unsafe { elem.assume_init() })
+    }
+
+    // Only the owner ever writes `buffer` or slots past `top`, so nobody can
+    // change what's being copied.
+    fn grow(&self, old: *mut Buffer<T>, top: isize, bottom: isize) -> *mut Buffer<T> {
+        let deque = &*self.deque;
+        let new = Buffer::alloc(unsafe { (*old).capacity() } * 2);
+        for index in top..bottom {
+            unsafe { (*new).write(index, (*old).read(index)) };
+        }
+        deque.buffer.store(new, Ordering::Release);
+        unsafe { deque.domain.retire(old) };
+        new
+    }
+}
+
+impl<T> Stealer<T> {
+    pub fn len(&self) -> usize {
+        self.deque.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // Oldest first. Losing a race for an element just means trying again
+    // with the next one, so this only gives up once the deque looks empty.
+    pub fn steal(&self) -> Option<T> {
+        let deque = &*self.deque;
+        let guard = deque.domain.guard();
+        loop {
+            let top = deque.top.load(Ordering::Acquire);
+            fence(Ordering::SeqCst);
+            let bottom = deque.bottom.load(Ordering::Acquire);
+            if top >= bottom {
+                return None;
+            }
+
+            // Any buffer published since `bottom` was read holds everything
+            // from the owner's `top` on, and if that's past ours the CAS
+            // below fails anyway.
+            let buffer = guard.protect(&deque.buffer);
+            let elem = unsafe { (*buffer).read(top) };
+            if deque
+                .top
+                .compare_exchange(top, top + 1, Ordering::SeqCst, Ordering::Relaxed)
+                .is_ok()
+            {
+                return Some(unsafe { elem.assume_init() });
+            }
+        }
+    }
+}
+
+impl<T> Clone for Stealer<T> {
+    fn clone(&self) -> Self {
+        Stealer {
+            deque: self.deque.clone(),
+        }
+    }
+}
+
+impl<T> Drop for WorkStealingDeque<T> {
+    fn drop(&mut self) {
+        let top = *self.top.get_mut();
+        let bottom = *self.bottom.get_mut();
+        let buffer = unsafe { Box::from_raw(*self.buffer.get_mut()) };
+        for index in top..bottom {
+            unsafe { buffer.read(index).assume_init_drop() };
+        }
+    }
+}
+
+unsafe impl<T: Send> Send for WorkStealingDeque<T> {}
+unsafe impl<T: Send> Sync for WorkStealingDeque<T> {}
+
+#[cfg(test)]
+mod tests {
+    use super::WorkStealingDeque;
+    use crate::concurrent::tests::Counted;
+    use std::collections::HashSet;
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::thread;
+
+    #[test]
+    fn basics() {
+        let (worker, stealer) = WorkStealingDeque::new();
+        assert!(worker.is_empty());
+        assert_eq!(worker.pop(), None);
+        assert_eq!(stealer.steal(), None);
+
+        for i in 1..=4 {
+            worker.push(i);
+        }
+        assert_eq!(stealer.len(), 4);
+
+        // The owner works from the bottom, thieves from the top
+        assert_eq!(worker.pop(), Some(4));
+        assert_eq!(stealer.steal(), Some(1));
+        assert_eq!(stealer.clone().steal(), Some(2));
+        assert_eq!(worker.pop(), Some(3));
+        assert_eq!(worker.pop(), None);
+        assert_eq!(stealer.steal(), None);
+
+        worker.push(5);
+        assert_eq!(worker.stealer().steal(), Some(5));
+        assert!(stealer.is_empty());
+    }
+
+    #[test]
+    fn grows() {
+        let (worker, stealer) = WorkStealingDeque::with_capacity(2);
+        assert_eq!(worker.capacity(), 16);
+
+        // Wrap around the ring a few times before it has to grow
+        for i in 0..40 {
+            worker.push(i);
+            assert_eq!(stealer.steal(), Some(i));
+        }
+        assert_eq!(worker.capacity(), 16);
+
+        for i in 0..100 {
+            worker.push(i);
+        }
+        assert_eq!(worker.capacity(), 128);
+        assert_eq!(worker.len(), 100);
+        assert_eq!(stealer.steal(), Some(0));
+        assert_eq!(worker.pop(), Some(99));
+        let rest: Vec<_> = std::iter::from_fn(|| stealer.steal()).collect();
+        assert_eq!(rest, (1..99).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn drops_every_element() {
+        let drops = Arc::new(AtomicUsize::new(0));
+        let (worker, stealer) = WorkStealingDeque::new();
+        for _ in 0..40 {
+            worker.push(Counted(drops.clone()));
+        }
+
+        drop(worker.pop());
+        drop(stealer.steal());
+        assert_eq!(drops.load(Ordering::Relaxed), 2);
+
+        // Stealers keep the deque alive after its worker is gone
+        drop(worker);
+        assert_eq!(drops.load(Ordering::Relaxed), 2);
+        drop(stealer.steal());
+        drop(stealer);
+        assert_eq!(drops.load(Ordering::Relaxed), 40);
+    }
+
+    #[test]
+    fn small_concurrent() {
+        let (worker, stealer) = WorkStealingDeque::with_capacity(2);
+
+        let thief = thread::spawn(move || {
+            let mut stolen = Vec::new();
+            for _ in 0..20 {
+                stolen.extend(stealer.steal());
+            }
+            stolen
+        });
+
+        let mut popped = Vec::new();
+        for i in 0..20 {
+            worker.push(i);
+            if i % 3 == 0 {
+                popped.extend(worker.pop());
+            }
+        }
+
+        popped.extend(thief.join().unwrap());
+        popped.extend(std::iter::from_fn(|| worker.pop()));
+        popped.sort();
+        assert_eq!(popped, (0..20).collect::<Vec<_>>());
+    }
+
+    // The owner keeps pushing and popping while thieves take what they can,
+    // and every element has to come out exactly once.
+    fn stress(thieves: usize, total: usize, pop_every: usize) {
+        let (worker, stealer) = WorkStealingDeque::new();
+        let done = Arc::new(AtomicBool::new(false));
+
+        let handles: Vec<_> = (0..thieves)
+            .map(|_| {
+                let stealer = stealer.clone();
+                let done = done.clone();
+                thread::spawn(move || {
+                    let mut stolen = Vec::new();
+                    loop {
+                        match stealer.steal() {
+                            Some(elem) => stolen.push(elem),
+                            None if done.load(Ordering::Acquire) => break,
+                            None => thread::yield_now(),
+                        }
+                    }
+                    stolen
+                })
+            })
+            .collect();
+
+        let mut seen = HashSet::new();
+        for i in 0..total {
+            worker.push(i);
+            if i % pop_every == 0 {
+                if let Some(elem) = worker.pop() {
+                    assert!(seen.insert(elem), "{} taken twice", elem);
+                }
+            }
+        }
+        while let Some(elem) = worker.pop() {
+            assert!(seen.insert(elem), "{} taken twice", elem);
+        }
+        done.store(true, Ordering::Release);
+
+        for handle in handles {
+            for elem in handle.join().unwrap() {
+                assert!(seen.insert(elem), "{} taken twice", elem);
+            }
+        }
+        assert_eq!(seen.len(), total);
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn stress_mostly_stolen() {
+        stress(7, 200_000, 5);
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn stress_contended_bottom() {
+        // Popping after every push keeps the deque at one or two elements,
+        // so the owner and the thieves keep fighting over the last one
+        stress(4, 200_000, 1);
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn stress_with_heap_elements() {
+        let (worker, stealer) = WorkStealingDeque::new();
+        let done = Arc::new(AtomicBool::new(false));
+
+        let handles: Vec<_> = (0..4)
+            .map(|_| {
+                let stealer = stealer.clone();
+                let done = done.clone();
+                thread::spawn(move || {
+                    let mut total = 0;
+                    while !done.load(Ordering::Acquire) || !stealer.is_empty() {
+                        if let Some(elem) = stealer.steal() {
+                            let elem: Vec<usize> = elem;
+                            total += elem.len();
+                        }
+                    }
+                    total
+                })
+            })
+            .collect();
+
+        let mut total = 0;
+        for i in 0..50_000 {
+            worker.push(vec![i; i % 8]);
+            if i % 2 == 0 {
+                total += worker.pop().map_or(0, |elem| elem.len());
+            }
+        }
+        done.store(true, Ordering::Release);
+
+        total += handles
+            .into_iter()
+            .map(|handle| handle.join().unwrap())
+            .sum::<usize>();
+        total += std::iter::from_fn(|| worker.pop())
+            .map(|elem| elem.len())
+            .sum::<usize>();
+        assert_eq!(total, (0..50_000).map(|i| i % 8).sum::<usize>());
+    }
+}
@@ -0,0 +1,249 @@
+use std::ptr;
+use std::sync::atomic::{fence, AtomicBool, AtomicPtr, AtomicUsize, Ordering};
+
+// Scan for reclaimable nodes once this many have been retired.
+const SCAN_THRESHOLD: usize = 64;
+
+// A hazard pointer domain. Threads publish the nodes they're about to
+// dereference in hazard records, and retired nodes are only freed once no
+// record points at them anymore. Everything still retired when the domain is
+// dropped is freed then, so owning structures don't leak on drop.
+pub(crate) struct Domain {
+    hazards: AtomicPtr<HazardRecord>,
+    retired: AtomicPtr<Retired>,
+    retired_count: AtomicUsize,
+}
+
+// Records are only ever added, never unlinked, so walking the list is always
+// safe while the domain is alive. Released records get reused.
+struct HazardRecord {
+    ptr: AtomicPtr<()>,
+    active: AtomicBool,
+    next: *mut HazardRecord,
+}
+
+struct Retired {
+    ptr: *mut (),
+    drop_fn: unsafe fn(*mut ()),
+    next: *mut Retired,
+}
+
+pub(crate) struct Guard<'a> {
+    record: &'a HazardRecord,
+}
+
+impl Domain {
+    pub(crate) const fn new() -> Self {
+        Domain {
+            hazards: AtomicPtr::new(ptr::null_mut()),
+            retired: AtomicPtr::new(ptr::null_mut()),
+            retired_count: AtomicUsize::new(0),
+        }
+    }
+
+    pub(crate) fn guard(&self) -> Guard<'_> {
+        let mut cur = self.hazards.load(Ordering::Acquire);
+        while !cur.is_null() {
+            let record = unsafe { &*cur };
+            if !record.active.load(Ordering::Relaxed)
+                && record
+                    .active
+                    .compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed)
+                    .is_ok()
+            {
+                return Guard { record };
+            }
+            cur = record.next;
+        }
+
+        let record = Box::into_raw(Box::new(HazardRecord {
+            ptr: AtomicPtr::new(ptr::null_mut()),
+            active: AtomicBool::new(true),
+            next: ptr::null_mut(),
+        }));
+
+        let mut head = self.hazards.load(Ordering::Relaxed);
+        loop {
+            unsafe { (*record).next = head };
+            match self.hazards.compare_exchange_weak(
+                head,
+                record,
+                Ordering::Release,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => break,
+                Err(new_head) => head = new_head,
+            }
+        }
+
+        Guard {
+            record: unsafe { &*record },
+        }
+    }
+
+    // Safety: `ptr` must come from `Box::into_raw`, must already be unreachable
+    // for threads that haven't protected it yet, and must only be retired once.
+    pub(crate) unsafe fn retire<T>(&self, ptr: *mut T) {
+        unsafe fn drop_box<T>(ptr: *mut ()) {
+            drop(Box::from_raw(ptr as *mut T));
+        }
+
+        self.push_retired(Box::into_raw(Box::new(Retired {
+            ptr: ptr as *mut (),
+            drop_fn: drop_box::<T>,
+            next: ptr::null_mut(),
+        })));
+
+        if self.retired_count.load(Ordering::Relaxed) >= SCAN_THRESHOLD {
+            self.scan();
+        }
+    }
+
+    fn push_retired(&self, retired: *mut Retired) {
+        let mut head = self.retired.load(Ordering::Relaxed);
+        loop {
+            unsafe { (*retired).next = head };
+            match self.retired.compare_exchange_weak(
+                head,
+                retired,
+                Ordering::Release,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => break,
+                Err(new_head) => head = new_head,
+            }
+        }
+        self.retired_count.fetch_add(1, Ordering::Relaxed);
+    }
+
+    // Takes the whole retired list, frees everything no hazard points at and
+    // puts the rest back for a later scan.
+    fn scan(&self) {
+        let mut cur = self.retired.swap(ptr::null_mut(), Ordering::Acquire);
+        fence(Ordering::SeqCst);
+
+        let mut protected = Vec::new();
+        let mut record = self.hazards.load(Ordering::Acquire);
+        while !record.is_null() {
+            let hazard = unsafe { (*record).ptr.load(Ordering::Acquire) };
+            if !hazard.is_null() {
+                protected.push(hazard);
+            }
+            record = unsafe { (*record).next };
+        }
+
+        while !cur.is_null() {
+            let retired = cur;
+            unsafe {
+                cur = (*retired).next;
+                if protected.contains(&(*retired).ptr) {
+                    self.retired_count.fetch_sub(1, Ordering::Relaxed);
+                    self.push_retired(retired);
+                } else {
+                    let retired = Box::from_raw(retired);
+                    self.retired_count.fetch_sub(1, Ordering::Relaxed);
+                    (retired.drop_fn)(retired.ptr);
+                }
+            }
+        }
+    }
+}
+
+impl Drop for Domain {
+    fn drop(&mut self) {
+        let mut cur = *self.retired.get_mut();
+        while !cur.is_null() {
+            let retired = unsafe { Box::from_raw(cur) };
+            cur = retired.next;
+            unsafe { (retired.drop_fn)(retired.ptr) };
+        }
+
+        let mut cur = *self.hazards.get_mut();
+        while !cur.is_null() {
+            let record = unsafe { Box::from_raw(cur) };
+            cur = record.next;
+        }
+    }
+}
+
+unsafe impl Send for Domain {}
+unsafe impl Sync for Domain {}
+
+impl Guard<'_> {
+    // Publishes the current value of `src` as hazardous and returns it once
+    // it's known to still be there after publishing, so it can't have been
+    // retired (and freed) in between.
+    pub(crate) fn protect<T>(&self, src: &AtomicPtr<T>) -> *mut T {
+        let mut ptr = src.load(Ordering::Relaxed);
+        loop {
+            self.record.ptr.store(ptr as *mut (), Ordering::SeqCst);
+            let current = src.load(Ordering::SeqCst);
+            if current == ptr {
+                return ptr;
+            }
+            ptr = current;
+        }
+    }
+}
+
+impl Drop for Guard<'_> {
+    fn drop(&mut self) {
+        self.record.ptr.store(ptr::null_mut(), Ordering::Release);
+        self.record.active.store(false, Ordering::Release);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Domain;
+    use crate::concurrent::tests::Counted;
+    use std::sync::atomic::{AtomicPtr, AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    #[test]
+    fn protected_nodes_survive_scans() {
+        let drops = Arc::new(AtomicUsize::new(0));
+        let domain = Domain::new();
+
+        let node = Box::into_raw(Box::new(Counted(drops.clone())));
+        let src = AtomicPtr::new(node);
+
+        let guard = domain.guard();
+        assert_eq!(guard.protect(&src), node);
+
+        src.store(std::ptr::null_mut(), Ordering::Relaxed);
+        unsafe { domain.retire(node) };
+        domain.scan();
+        assert_eq!(drops.load(Ordering::Relaxed), 0);
+
+        drop(guard);
+        domain.scan();
+        assert_eq!(drops.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn records_are_reused() {
+        let domain = Domain::new();
+        let first = domain.guard().record as *const _;
+        let second = domain.guard().record as *const _;
+        assert_eq!(first, second);
+
+        let a = domain.guard();
+        let b = domain.guard();
+        assert!(!std::ptr::eq(a.record, b.record));
+    }
+
+    #[test]
+    fn drop_frees_retired() {
+        let drops = Arc::new(AtomicUsize::new(0));
+        let domain = Domain::new();
+
+        for _ in 0..10 {
+            let node = Box::into_raw(Box::new(Counted(drops.clone())));
+            unsafe { domain.retire(node) };
+        }
+
+        drop(domain);
+        assert_eq!(drops.load(Ordering::Relaxed), 10);
+    }
+}
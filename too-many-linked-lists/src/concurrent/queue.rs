@@ -0,0 +1,298 @@
+use std::marker::PhantomData;
+use std::mem::MaybeUninit;
+use std::ptr;
+use std::sync::atomic::{AtomicPtr, AtomicUsize, Ordering};
+
+use super::hazard::Domain;
+
+// Michael and Scott's lock-free queue. `head` always points at a sentinel
+// node whose element has already been taken (or never existed), and the
+// first real element lives in the sentinel's successor. `tail` may lag one
+// node behind the real tail; whoever notices helps swing it forward.
+pub struct Queue<T> {
+    head: AtomicPtr<Node<T>>,
+    tail: AtomicPtr<Node<T>>,
+    len: AtomicUsize,
+    domain: Domain,
+    _boo: PhantomData<T>,
+}
+
+struct Node<T> {
+    elem: MaybeUninit<T>,
+    next: AtomicPtr<Node<T>>,
+}
+
+impl<T> Node<T> {
+    fn new(elem: MaybeUninit<T>) -> *mut Self {
+        Box::into_raw(Box::new(Node {
+            elem,
+            next: AtomicPtr::new(ptr::null_mut()),
+        }))
+    }
+}
+
+impl<T> Default for Queue<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Queue<T> {
+    pub fn new() -> Self {
+        let sentinel = Node::new(MaybeUninit::uninit());
+        Queue {
+            head: AtomicPtr::new(sentinel),
+            tail: AtomicPtr::new(sentinel),
+            len: AtomicUsize::new(0),
+            domain: Domain::new(),
+            _boo: PhantomData,
+        }
+    }
+
+    // Only a snapshot: other threads may push or pop right after it's read.
+    // It's counted before an element is linked in and after it's taken out,
+    // so it can run ahead of the real length but never behind it.
+    pub fn len(&self) -> usize {
+        self.len.load(Ordering::Relaxed)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        let guard = self.domain.guard();
+        let head = guard.protect(&self.head);
+        unsafe { (*head).next.load(Ordering::Acquire).is_null() }
+    }
+
+    pub fn push(&self, elem: T) {
+        let new_node = Node::new(MaybeUninit::new(elem));
+        self.len.fetch_add(1, Ordering::Relaxed);
+
+        let guard = self.domain.guard();
+        loop {
+            let tail = guard.protect(&self.tail);
+            let next = unsafe { (*tail).next.load(Ordering::Acquire) };
+
+            if !next.is_null() {
+                // `tail` is lagging behind, help it along and retry
+                let _ =
+                    self.tail
+                        .compare_exchange(tail, next, Ordering::Release, Ordering::Relaxed);
+                continue;
+            }
+
+            let linked = unsafe {
+                (*tail).next.compare_exchange(
+                    ptr::null_mut(),
+                    new_node,
+                    Ordering::Release,
+                    Ordering::Relaxed,
+                )
+            };
+
+            if linked.is_ok() {
+                let _ = self.tail.compare_exchange(
+                    tail,
+                    new_node,
+                    Ordering::Release,
+                    Ordering::Relaxed,
+                );
+                return;
+            }
+        }
+    }
+
+    pub fn pop(&self) -> Option<T> {
+        let head_guard = self.domain.guard();
+        let next_guard = self.domain.guard();
+        loop {
+            let head = head_guard.protect(&self.head);
+            let next = next_guard.protect(unsafe { &(*head).next });
+
+            // `next` can only be retired once `head` has been popped past it,
+            // so if `head` is still in place then `next` is safe to read.
+            if self.head.load(Ordering::Acquire) != head {
+                continue;
+            }
+
+            if next.is_null() {
+                return None;
+            }
+
+            let tail = self.tail.load(Ordering::Acquire);
+            if head == tail {
+                let _ =
+                    self.tail
+                        .compare_exchange(tail, next, Ordering::Release, Ordering::Relaxed);
+                continue;
+            }
+
+            if self
+                .head
+                .compare_exchange(head, next, Ordering::AcqRel, Ordering::Relaxed)
+                .is_ok()
+            {
+                // `next` is the new sentinel and its element is ours now
+                let elem = unsafe { ptr::read((*next).elem.as_ptr()) };
+                drop(next_guard);
+                drop(head_guard);
+
+                unsafe { self.domain.retire(head) };
+                self.len.fetch_sub(1, Ordering::Relaxed);
+                return Some(elem);
+            }
+        }
+    }
+}
+
+impl<T> Drop for Queue<T> {
+    fn drop(&mut self) {
+        let sentinel = unsafe { Box::from_raw(*self.head.get_mut()) };
+        let mut cur = sentinel.next.load(Ordering::Relaxed);
+        while !cur.is_null() {
+            let mut node = unsafe { Box::from_raw(cur) };
+            cur = node.next.load(Ordering::Relaxed);
+            unsafe { node.elem.assume_init_drop() };
+        }
+    }
+}
+
+unsafe impl<T: Send> Send for Queue<T> {}
+unsafe impl<T: Send> Sync for Queue<T> {}
+
+#[cfg(test)]
+mod tests {
+    use super::Queue;
+    use crate::concurrent::tests::Counted;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::thread;
+
+    #[test]
+    fn basics() {
+        let queue = Queue::new();
+        assert!(queue.is_empty());
+        assert_eq!(queue.len(), 0);
+        assert_eq!(queue.pop(), None);
+
+        queue.push(1);
+        queue.push(2);
+        queue.push(3);
+        assert!(!queue.is_empty());
+        assert_eq!(queue.len(), 3);
+        assert_eq!(queue.pop(), Some(1));
+        assert_eq!(queue.pop(), Some(2));
+
+        queue.push(4);
+        assert_eq!(queue.pop(), Some(3));
+        assert_eq!(queue.pop(), Some(4));
+        assert_eq!(queue.pop(), None);
+        assert!(queue.is_empty());
+        assert_eq!(queue.len(), 0);
+    }
+
+    #[test]
+    fn drains_on_drop() {
+        let drops = Arc::new(AtomicUsize::new(0));
+        let queue = Queue::new();
+        for _ in 0..10 {
+            queue.push(Counted(drops.clone()));
+        }
+
+        drop(queue.pop());
+        drop(queue.pop());
+        assert_eq!(drops.load(Ordering::Relaxed), 2);
+
+        drop(queue);
+        assert_eq!(drops.load(Ordering::Relaxed), 10);
+
+        // An empty queue only has its sentinel to free
+        drop(Queue::<Counted>::new());
+    }
+
+    #[test]
+    fn small_concurrent() {
+        let queue = Arc::new(Queue::new());
+
+        let producer = {
+            let queue = queue.clone();
+            thread::spawn(move || {
+                for i in 0..20 {
+                    queue.push(i);
+                }
+            })
+        };
+
+        let mut popped = Vec::new();
+        while popped.len() < 20 {
+            if let Some(elem) = queue.pop() {
+                popped.push(elem);
+            }
+        }
+        producer.join().unwrap();
+
+        assert_eq!(popped, (0..20).collect::<Vec<_>>());
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn mpmc() {
+        const PRODUCERS: usize = 8;
+        const CONSUMERS: usize = 8;
+        const PER_PRODUCER: usize = 20_000;
+        const TOTAL: usize = PRODUCERS * PER_PRODUCER;
+
+        let queue = Arc::new(Queue::new());
+        let popped = Arc::new(AtomicUsize::new(0));
+
+        let producers: Vec<_> = (0..PRODUCERS)
+            .map(|p| {
+                let queue = queue.clone();
+                thread::spawn(move || {
+                    for i in 0..PER_PRODUCER {
+                        queue.push((p, i));
+                    }
+                })
+            })
+            .collect();
+
+        let consumers: Vec<_> = (0..CONSUMERS)
+            .map(|_| {
+                let queue = queue.clone();
+                let popped = popped.clone();
+                thread::spawn(move || {
+                    let mut got = Vec::new();
+                    while popped.load(Ordering::Relaxed) < TOTAL {
+                        if let Some(elem) = queue.pop() {
+                            popped.fetch_add(1, Ordering::Relaxed);
+                            got.push(elem);
+                        }
+                    }
+                    got
+                })
+            })
+            .collect();
+
+        for producer in producers {
+            producer.join().unwrap();
+        }
+
+        let mut seen = vec![vec![false; PER_PRODUCER]; PRODUCERS];
+        for consumer in consumers {
+            let got = consumer.join().unwrap();
+
+            // Each consumer sees every producer's elements in push order
+            let mut last = [None; PRODUCERS];
+            for (p, i) in got {
+                assert!(last[p] < Some(i), "out of order from producer {}", p);
+                last[p] = Some(i);
+
+                assert!(!seen[p][i], "({}, {}) popped twice", p, i);
+                seen[p][i] = true;
+            }
+        }
+
+        assert!(seen.iter().flatten().all(|&seen| seen), "element lost");
+        assert!(queue.is_empty());
+        assert_eq!(queue.len(), 0);
+    }
+}
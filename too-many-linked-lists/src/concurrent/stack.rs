@@ -0,0 +1,326 @@
+use std::marker::PhantomData;
+use std::mem::ManuallyDrop;
+use std::ptr;
+use std::sync::atomic::{AtomicPtr, AtomicUsize, Ordering};
+use std::sync::OnceLock;
+
+use super::hazard::Domain;
+
+// A node's `state`: whether its element has been moved out, and how many
+// `peek`s are cloning it.
+const TAKEN: usize = 1;
+const READER: usize = 2;
+
+// Treiber's lock-free stack. Popped nodes are retired to a hazard pointer
+// domain instead of being freed right away, so threads that still hold a
+// pointer to them can keep reading.
+//
+// `pop` moves the element out unless a `peek` is cloning it right then. Only
+// then, rather than wait, it makes a clone of its own with the function the
+// `peek` left in the node, and the original is dropped with the node.
+pub struct Stack<T> {
+    head: AtomicPtr<Node<T>>,
+    domain: Domain,
+    _boo: PhantomData<T>,
+}
+
+struct Node<T> {
+    elem: ManuallyDrop<T>,
+    state: AtomicUsize,
+    clone: OnceLock<fn(&T) -> T>,
+    next: *mut Node<T>,
+}
+
+impl<T> Drop for Node<T> {
+    fn drop(&mut self) {
+        if *self.state.get_mut() & TAKEN == 0 {
+            unsafe { ManuallyDrop::drop(&mut self.elem) };
+        }
+    }
+}
+
+// Stops counting a `peek` as a reader, even if cloning panics.
+struct Reading<'a>(&'a AtomicUsize);
+
+impl Drop for Reading<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(READER, Ordering::Release);
+    }
+}
+
+impl<T> Default for Stack<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Stack<T> {
+    pub fn new() -> Self {
+        Stack {
+            head: AtomicPtr::new(ptr::null_mut()),
+            domain: Domain::new(),
+            _boo: PhantomData,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.head.load(Ordering::Acquire).is_null()
+    }
+
+    pub fn push(&self, elem: T) {
+        let new_node = Box::into_raw(Box::new(Node {
+            elem: ManuallyDrop::new(elem),
+            state: AtomicUsize::new(0),
+            clone: OnceLock::new(),
+            next: ptr::null_mut(),
+        }));
+
+        let mut head = self.head.load(Ordering::Relaxed);
+        loop {
+            unsafe { (*new_node).next = head };
+            match self.head.compare_exchange_weak(
+                head,
+                new_node,
+                Ordering::Release,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => return,
+                Err(new_head) => head = new_head,
+            }
+        }
+    }
+
+    pub fn pop(&self) -> Option<T> {
+        let guard = self.domain.guard();
+        loop {
+            let head = guard.protect(&self.head);
+            if head.is_null() {
+                return None;
+            }
+
+            let next = unsafe { (*head).next };
+            if self
+                .head
+                .compare_exchange(head, next, Ordering::AcqRel, Ordering::Relaxed)
+                .is_ok()
+            {
+                let node = unsafe { &*head };
+                let taken = node
+                    .state
+                    .compare_exchange(0, TAKEN, Ordering::AcqRel, Ordering::Acquire)
+                    .is_ok();
+
+                // Our guard keeps the node around until we're done with it
+                unsafe { self.domain.retire(head) };
+                let elem = if taken {
+                    unsafe { ptr::read(&*node.elem) }
+                } else {
+                    // Only a `peek` registers as a reader, and it sets
+                    // `clone` before doing so
+                    let clone = node.clone.get().unwrap();
+                    clone(&node.elem)
+                };
+                drop(guard);
+                return Some(elem);
+            }
+        }
+    }
+
+    pub fn peek(&self) -> Option<T>
+    where
+        T: Clone + Sync,
+    {
+        let guard = self.domain.guard();
+        loop {
+            let head = guard.protect(&self.head);
+            let node = unsafe { head.as_ref()? };
+
+            node.clone.get_or_init(|| T::clone);
+            let state = node.state.fetch_add(READER, Ordering::AcqRel);
+            let _reading = Reading(&node.state);
+
+            // Once registered, a `pop` can't move the element out from under
+            // us. If one already has, or the node is no longer on top, start
+            // over from the new head.
+            if state & TAKEN != 0 || self.head.load(Ordering::Acquire) != head {
+                continue;
+            }
+            return Some((*node.elem).clone());
+        }
+    }
+}
+
+impl<T> Drop for Stack<T> {
+    fn drop(&mut self) {
+        let mut cur = *self.head.get_mut();
+        while !cur.is_null() {
+            let node = unsafe { Box::from_raw(cur) };
+            cur = node.next;
+        }
+    }
+}
+
+unsafe impl<T: Send> Send for Stack<T> {}
+unsafe impl<T: Send> Sync for Stack<T> {}
+
+#[cfg(test)]
+mod tests {
+    use super::{Stack, READER};
+    use crate::concurrent::tests::Counted;
+    use std::collections::HashSet;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::thread;
+
+    #[test]
+    fn basics() {
+        let stack = Stack::new();
+        assert!(stack.is_empty());
+        assert_eq!(stack.pop(), None);
+        assert_eq!(stack.peek(), None);
+
+        stack.push(1);
+        stack.push(2);
+        stack.push(3);
+        assert!(!stack.is_empty());
+        assert_eq!(stack.peek(), Some(3));
+        assert_eq!(stack.pop(), Some(3));
+        assert_eq!(stack.pop(), Some(2));
+
+        stack.push(4);
+        assert_eq!(stack.pop(), Some(4));
+        assert_eq!(stack.pop(), Some(1));
+        assert_eq!(stack.pop(), None);
+        assert!(stack.is_empty());
+    }
+
+    #[test]
+    fn drops_every_element() {
+        let drops = Arc::new(AtomicUsize::new(0));
+        let stack = Stack::new();
+        for _ in 0..10 {
+            stack.push(Counted(drops.clone()));
+        }
+
+        drop(stack.pop());
+        drop(stack.pop());
+        assert_eq!(drops.load(Ordering::Relaxed), 2);
+
+        drop(stack);
+        assert_eq!(drops.load(Ordering::Relaxed), 10);
+    }
+
+    #[test]
+    fn pop_clones_while_a_peek_is_reading() {
+        let drops = Arc::new(AtomicUsize::new(0));
+        let stack = Stack::new();
+        stack.push(Arc::new(Counted(drops.clone())));
+
+        // Pretend a `peek` is halfway through cloning the top element
+        let node = unsafe { &*stack.head.load(Ordering::Relaxed) };
+        node.clone.get_or_init(|| Arc::clone);
+        node.state.fetch_add(READER, Ordering::Relaxed);
+
+        let elem = stack.pop().unwrap();
+        assert_eq!(Arc::strong_count(&elem), 2);
+        drop(elem);
+        assert_eq!(drops.load(Ordering::Relaxed), 0);
+
+        // The original goes with the node
+        node.state.fetch_sub(READER, Ordering::Relaxed);
+        drop(stack);
+        assert_eq!(drops.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn small_concurrent() {
+        let stack = Arc::new(Stack::new());
+
+        let handles: Vec<_> = (0..2)
+            .map(|t| {
+                let stack = stack.clone();
+                thread::spawn(move || {
+                    for i in 0..10 {
+                        stack.push(t * 10 + i);
+                        stack.peek();
+                        stack.pop();
+                    }
+                })
+            })
+            .collect();
+
+        for handle in handles {
+            handle.join().unwrap();
+        }
+        assert_eq!(stack.pop(), None);
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn stress() {
+        const THREADS: usize = 8;
+        const PER_THREAD: usize = 10_000;
+
+        let stack = Arc::new(Stack::new());
+
+        let handles: Vec<_> = (0..THREADS)
+            .map(|t| {
+                let stack = stack.clone();
+                thread::spawn(move || {
+                    let mut popped = Vec::new();
+                    for i in 0..PER_THREAD {
+                        stack.push(t * PER_THREAD + i);
+                        if i % 3 == 0 {
+                            stack.peek();
+                        }
+                        if i % 2 == 0 {
+                            popped.extend(stack.pop());
+                        }
+                    }
+                    popped
+                })
+            })
+            .collect();
+
+        let mut seen = HashSet::new();
+        for handle in handles {
+            for elem in handle.join().unwrap() {
+                assert!(seen.insert(elem), "{} popped twice", elem);
+            }
+        }
+        while let Some(elem) = stack.pop() {
+            assert!(seen.insert(elem), "{} popped twice", elem);
+        }
+
+        assert_eq!(seen.len(), THREADS * PER_THREAD);
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn stress_with_heap_elements() {
+        let stack = Arc::new(Stack::new());
+
+        let handles: Vec<_> = (0..8)
+            .map(|t| {
+                let stack = stack.clone();
+                thread::spawn(move || {
+                    for i in 0..5_000 {
+                        stack.push(vec![t; i % 8]);
+                        if let Some(mut elem) = stack.peek() {
+                            elem.push(0);
+                        }
+                        if let Some(mut elem) = stack.pop() {
+                            elem.clear();
+                            elem.shrink_to_fit();
+                        }
+                    }
+                })
+            })
+            .collect();
+
+        for handle in handles {
+            handle.join().unwrap();
+        }
+        assert!(stack.is_empty());
+    }
+}
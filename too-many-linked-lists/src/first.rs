@@ -1,14 +1,81 @@
-use std::mem;
+use alloc::boxed::Box;
+use alloc::string::ToString;
+use core::cmp::Ordering;
+use core::fmt;
+use core::hash::{Hash, Hasher};
+use core::mem;
+
+use crate::traits::{Collection, Stack};
+use crate::viz::{self, Graph, ToDot};
 
 pub struct List {
     head: Link,
 }
 
+pub struct Iter<'a> {
+    next: &'a Link,
+}
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = &'a i32;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.next {
+            Link::Empty => None,
+            Link::More(node) => {
+                self.next = &node.next;
+                Some(&node.elem)
+            }
+        }
+    }
+}
+
+pub struct IterMut<'a> {
+    next: Option<&'a mut Node>,
+}
+
+impl<'a> Iterator for IterMut<'a> {
+    type Item = &'a mut i32;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next.take().map(|node| {
+            self.next = match &mut node.next {
+                Link::Empty => None,
+                Link::More(next) => Some(next),
+            };
+            &mut node.elem
+        })
+    }
+}
+
+pub struct IntoIter(List);
+
+impl Iterator for IntoIter {
+    type Item = i32;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.pop()
+    }
+}
+
 impl List {
     pub fn new() -> Self {
         List { head: Link::Empty }
     }
 
+    pub fn iter(&self) -> Iter<'_> {
+        Iter { next: &self.head }
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_> {
+        IterMut {
+            next: match &mut self.head {
+                Link::Empty => None,
+                Link::More(node) => Some(node),
+            },
+        }
+    }
+
     pub fn push(&mut self, elem: i32) {
         let new_node = Node {
             elem,
@@ -29,6 +96,110 @@ impl List {
     }
 }
 
+impl Default for List {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clone for List {
+    fn clone(&self) -> Self {
+        self.iter().copied().collect()
+    }
+}
+
+impl fmt::Debug for List {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl PartialEq for List {
+    fn eq(&self, other: &Self) -> bool {
+        self.iter().eq(other.iter())
+    }
+}
+
+impl Eq for List {}
+
+impl PartialOrd for List {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for List {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.iter().cmp(other.iter())
+    }
+}
+
+impl Hash for List {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.len().hash(state);
+        for elem in self.iter() {
+            elem.hash(state);
+        }
+    }
+}
+
+// Collecting keeps the iterator's order, so the first element ends up on top.
+impl FromIterator<i32> for List {
+    fn from_iter<I: IntoIterator<Item = i32>>(iter: I) -> Self {
+        let mut list = List::new();
+        list.extend(iter);
+        list
+    }
+}
+
+// Appends after the bottom of the stack, in order.
+impl Extend<i32> for List {
+    fn extend<I: IntoIterator<Item = i32>>(&mut self, iter: I) {
+        let mut link = &mut self.head;
+        while let Link::More(node) = link {
+            link = &mut node.next;
+        }
+
+        for elem in iter {
+            *link = Link::More(Box::new(Node {
+                elem,
+                next: Link::Empty,
+            }));
+            link = match link {
+                Link::More(node) => &mut node.next,
+                Link::Empty => unreachable!(),
+            };
+        }
+    }
+}
+
+impl IntoIterator for List {
+    type Item = i32;
+    type IntoIter = IntoIter;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter(self)
+    }
+}
+
+impl<'a> IntoIterator for &'a List {
+    type Item = &'a i32;
+    type IntoIter = Iter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a mut List {
+    type Item = &'a mut i32;
+    type IntoIter = IterMut<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
 impl Drop for List {
     fn drop(&mut self) {
         let mut cur_link = mem::replace(&mut self.head, Link::Empty);
@@ -48,9 +219,58 @@ struct Node {
     next: Link,
 }
 
+impl Collection<i32> for List {
+    type Ref<'a>
+        = &'a i32
+    where
+        Self: 'a;
+
+    fn len(&self) -> usize {
+        self.iter().count()
+    }
+
+    fn clear(&mut self) {
+        while self.pop().is_some() {}
+    }
+}
+
+impl Stack<i32> for List {
+    fn push(&mut self, elem: i32) {
+        List::push(self, elem)
+    }
+
+    fn pop(&mut self) -> Option<i32> {
+        List::pop(self)
+    }
+
+    fn peek(&self) -> Option<&i32> {
+        match &self.head {
+            Link::Empty => None,
+            Link::More(node) => Some(&node.elem),
+        }
+    }
+}
+
+impl ToDot for List {
+    fn write_dot(&self, name: &str, graph: &mut Graph) {
+        let mut from = graph.root(name);
+        let mut attrs = "label=\"head\"";
+        let mut cur = &self.head;
+        while let Link::More(node) = cur {
+            let ptr: *const Node = &**node;
+            graph.node(ptr, &node.elem.to_string());
+            graph.edge(&from, &viz::id(ptr), attrs);
+            from = viz::id(ptr);
+            attrs = "";
+            cur = &node.next;
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::List;
+    use crate::test_util::hash_of;
 
     #[test]
     fn basics() {
@@ -70,4 +290,45 @@ mod tests {
         assert_eq!(list.pop(), Some(1));
         assert!(list.pop().is_none());
     }
+
+    #[test]
+    fn iter() {
+        let mut list: List = (1..=3).collect();
+        assert_eq!(list.iter().collect::<Vec<_>>(), vec![&1, &2, &3]);
+
+        for elem in &mut list {
+            *elem *= 10;
+        }
+        assert_eq!(list.iter().collect::<Vec<_>>(), vec![&10, &20, &30]);
+        assert_eq!(list.into_iter().collect::<Vec<_>>(), vec![10, 20, 30]);
+    }
+
+    #[test]
+    fn std_traits() {
+        let mut list: List = (1..=3).collect();
+        assert_eq!(list.pop(), Some(1));
+        list.extend(vec![4, 5]);
+        assert_eq!(format!("{:?}", list), "[2, 3, 4, 5]");
+
+        let copy = list.clone();
+        assert_eq!(copy, list);
+        assert_eq!(hash_of(&copy), hash_of(&list));
+        assert_eq!(List::default(), List::new());
+
+        let mut bigger = copy.clone();
+        bigger.push(9);
+        assert_ne!(bigger, list);
+        assert!(bigger > list);
+        assert!(List::new() < list);
+        assert!((1..=2).collect::<List>() < list);
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn deep_clone_and_drop() {
+        let list: List = (0..1_000_000).collect();
+        let copy = list.clone();
+        assert_eq!(copy.iter().count(), 1_000_000);
+        assert!(copy == list);
+    }
 }
@@ -1,8 +1,22 @@
-use std::cell::{Ref, RefCell, RefMut};
-use std::rc::Rc;
+use alloc::rc::Rc;
+use alloc::string::String;
+use core::cell::{Ref, RefCell, RefMut};
+use core::cmp::Ordering;
+use core::fmt;
+use core::hash::{Hash, Hasher};
+use core::ptr;
+
+use crate::traits::{Collection, Deque, Queue, Stack};
+use crate::viz::{self, Graph, ToDot};
 
 type Link<T> = Option<Rc<RefCell<Node<T>>>>;
 
+// Nodes are only ever unlinked through `&mut List`, so every node reachable
+// from a list borrowed for `'a` is kept alive by the list for at least `'a`.
+unsafe fn node_ref<'a, T>(link: &Link<T>) -> Option<&'a RefCell<Node<T>>> {
+    link.as_ref().map(|node| &*Rc::as_ptr(node))
+}
+
 pub struct IntoIter<T>(List<T>);
 
 impl<T> Iterator for IntoIter<T> {
@@ -19,6 +33,162 @@ impl<T> DoubleEndedIterator for IntoIter<T> {
     }
 }
 
+pub struct Iter<'a, T> {
+    front: Option<&'a RefCell<Node<T>>>,
+    back: Option<&'a RefCell<Node<T>>>,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = Ref<'a, T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.front.take().map(|node| {
+            if self.back.is_some_and(|back| ptr::eq(back, node)) {
+                self.back = None;
+            } else {
+                self.front = unsafe { node_ref(&node.borrow().next) };
+            }
+            Ref::map(node.borrow(), |node| &node.elem)
+        })
+    }
+}
+
+impl<T> DoubleEndedIterator for Iter<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.back.take().map(|node| {
+            if self.front.is_some_and(|front| ptr::eq(front, node)) {
+                self.front = None;
+            } else {
+                self.back = unsafe { node_ref(&node.borrow().prev) };
+            }
+            Ref::map(node.borrow(), |node| &node.elem)
+        })
+    }
+}
+
+pub struct IterMut<'a, T> {
+    front: Option<&'a RefCell<Node<T>>>,
+    back: Option<&'a RefCell<Node<T>>>,
+}
+
+impl<'a, T> Iterator for IterMut<'a, T> {
+    type Item = RefMut<'a, T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.front.take().map(|node| {
+            if self.back.is_some_and(|back| ptr::eq(back, node)) {
+                self.back = None;
+            } else {
+                self.front = unsafe { node_ref(&node.borrow().next) };
+            }
+            RefMut::map(node.borrow_mut(), |node| &mut node.elem)
+        })
+    }
+}
+
+impl<T> DoubleEndedIterator for IterMut<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.back.take().map(|node| {
+            if self.front.is_some_and(|front| ptr::eq(front, node)) {
+                self.front = None;
+            } else {
+                self.back = unsafe { node_ref(&node.borrow().prev) };
+            }
+            RefMut::map(node.borrow_mut(), |node| &mut node.elem)
+        })
+    }
+}
+
+pub struct Cursor<'a, T> {
+    list: &'a mut List<T>,
+    cur: Link<T>,
+}
+
+// Like `sixth::CursorMut`, the cursor starts on a "ghost" position that sits
+// between the back and the front of the list.
+impl<T> Cursor<'_, T> {
+    pub fn move_next(&mut self) {
+        self.cur = match self.cur.take() {
+            Some(cur) => cur.borrow().next.clone(),
+            None => self.list.head.clone(),
+        };
+    }
+
+    pub fn move_prev(&mut self) {
+        self.cur = match self.cur.take() {
+            Some(cur) => cur.borrow().prev.clone(),
+            None => self.list.tail.clone(),
+        };
+    }
+
+    pub fn current(&self) -> Option<Ref<'_, T>> {
+        self.cur
+            .as_ref()
+            .map(|node| Ref::map(node.borrow(), |node| &node.elem))
+    }
+
+    pub fn current_mut(&mut self) -> Option<RefMut<'_, T>> {
+        self.cur
+            .as_ref()
+            .map(|node| RefMut::map(node.borrow_mut(), |node| &mut node.elem))
+    }
+
+    pub fn insert_before(&mut self, elem: T) {
+        match &self.cur {
+            None => self.list.push_back(elem),
+            Some(cur) => {
+                let new_node = Node::new(elem);
+                match cur.borrow_mut().prev.take() {
+                    None => self.list.head = Some(new_node.clone()),
+                    Some(prev) => {
+                        prev.borrow_mut().next = Some(new_node.clone());
+                        new_node.borrow_mut().prev = Some(prev);
+                    }
+                }
+                new_node.borrow_mut().next = Some(cur.clone());
+                cur.borrow_mut().prev = Some(new_node);
+            }
+        }
+    }
+
+    pub fn insert_after(&mut self, elem: T) {
+        match &self.cur {
+            None => self.list.push_front(elem),
+            Some(cur) => {
+                let new_node = Node::new(elem);
+                match cur.borrow_mut().next.take() {
+                    None => self.list.tail = Some(new_node.clone()),
+                    Some(next) => {
+                        next.borrow_mut().prev = Some(new_node.clone());
+                        new_node.borrow_mut().next = Some(next);
+                    }
+                }
+                new_node.borrow_mut().prev = Some(cur.clone());
+                cur.borrow_mut().next = Some(new_node);
+            }
+        }
+    }
+
+    pub fn remove_current(&mut self) -> Option<T> {
+        self.cur.take().map(|old| {
+            let prev = old.borrow_mut().prev.take();
+            let next = old.borrow_mut().next.take();
+
+            match &prev {
+                None => self.list.head = next.clone(),
+                Some(prev) => prev.borrow_mut().next = next.clone(),
+            }
+            match &next {
+                None => self.list.tail = prev.clone(),
+                Some(next) => next.borrow_mut().prev = prev.clone(),
+            }
+
+            self.cur = next;
+            Rc::try_unwrap(old).ok().unwrap().into_inner().elem
+        })
+    }
+}
+
 pub struct List<T> {
     head: Link<T>,
     tail: Link<T>,
@@ -32,8 +202,29 @@ impl<T> List<T> {
         }
     }
 
-    pub fn into_iter(self) -> IntoIter<T> {
-        IntoIter(self)
+    pub fn iter(&self) -> Iter<'_, T> {
+        unsafe {
+            Iter {
+                front: node_ref(&self.head),
+                back: node_ref(&self.tail),
+            }
+        }
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        unsafe {
+            IterMut {
+                front: node_ref(&self.head),
+                back: node_ref(&self.tail),
+            }
+        }
+    }
+
+    pub fn cursor(&mut self) -> Cursor<'_, T> {
+        Cursor {
+            list: self,
+            cur: None,
+        }
     }
 
     pub fn push_front(&mut self, elem: T) {
@@ -98,31 +289,135 @@ impl<T> List<T> {
         })
     }
 
-    pub fn peek_front(&self) -> Option<Ref<T>> {
+    pub fn peek_front(&self) -> Option<Ref<'_, T>> {
         self.head
             .as_ref()
             .map(|node| Ref::map(node.borrow(), |node| &node.elem))
     }
 
-    pub fn peek_front_mut(&self) -> Option<RefMut<T>> {
+    pub fn peek_front_mut(&self) -> Option<RefMut<'_, T>> {
         self.head
             .as_ref()
             .map(|node| RefMut::map(node.borrow_mut(), |node| &mut node.elem))
     }
 
-    pub fn peek_back(&self) -> Option<Ref<T>> {
+    pub fn peek_back(&self) -> Option<Ref<'_, T>> {
         self.tail
             .as_ref()
             .map(|node| Ref::map(node.borrow(), |node| &node.elem))
     }
 
-    pub fn peek_back_mut(&self) -> Option<RefMut<T>> {
+    pub fn peek_back_mut(&self) -> Option<RefMut<'_, T>> {
         self.tail
             .as_ref()
             .map(|node| RefMut::map(node.borrow_mut(), |node| &mut node.elem))
     }
 }
 
+impl<T> Default for List<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Clone> Clone for List<T> {
+    fn clone(&self) -> Self {
+        self.iter().map(|elem| elem.clone()).collect()
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for List<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+// `Ref` doesn't forward the comparison traits, so these can't just defer to
+// `Iterator::eq` and friends.
+impl<T: PartialEq> PartialEq for List<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.iter().zip(other.iter()).all(|(a, b)| *a == *b)
+    }
+}
+
+impl<T: Eq> Eq for List<T> {}
+
+impl<T: PartialOrd> PartialOrd for List<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        for (a, b) in self.iter().zip(other.iter()) {
+            match (*a).partial_cmp(&*b) {
+                Some(Ordering::Equal) => {}
+                non_eq => return non_eq,
+            }
+        }
+        self.len().partial_cmp(&other.len())
+    }
+}
+
+impl<T: Ord> Ord for List<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        for (a, b) in self.iter().zip(other.iter()) {
+            match (*a).cmp(&*b) {
+                Ordering::Equal => {}
+                non_eq => return non_eq,
+            }
+        }
+        self.len().cmp(&other.len())
+    }
+}
+
+impl<T: Hash> Hash for List<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.len().hash(state);
+        for elem in self.iter() {
+            elem.hash(state);
+        }
+    }
+}
+
+impl<T> FromIterator<T> for List<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut list = List::new();
+        list.extend(iter);
+        list
+    }
+}
+
+impl<T> Extend<T> for List<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for elem in iter {
+            self.push_back(elem);
+        }
+    }
+}
+
+impl<T> IntoIterator for List<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter(self)
+    }
+}
+
+impl<'a, T> IntoIterator for &'a List<T> {
+    type Item = Ref<'a, T>;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a mut List<T> {
+    type Item = RefMut<'a, T>;
+    type IntoIter = IterMut<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
 impl<T> Drop for List<T> {
     fn drop(&mut self) {
         while self.pop_front().is_some() {}
@@ -145,9 +440,133 @@ impl<T> Node<T> {
     }
 }
 
+impl<T> Collection<T> for List<T> {
+    type Ref<'a>
+        = Ref<'a, T>
+    where
+        Self: 'a;
+
+    fn len(&self) -> usize {
+        self.iter().count()
+    }
+
+    fn clear(&mut self) {
+        while self.pop_front().is_some() {}
+    }
+}
+
+impl<T> Stack<T> for List<T> {
+    fn push(&mut self, elem: T) {
+        self.push_front(elem)
+    }
+
+    fn pop(&mut self) -> Option<T> {
+        self.pop_front()
+    }
+
+    fn peek(&self) -> Option<Ref<'_, T>> {
+        self.peek_front()
+    }
+}
+
+impl<T> Queue<T> for List<T> {
+    fn push(&mut self, elem: T) {
+        self.push_back(elem)
+    }
+
+    fn pop(&mut self) -> Option<T> {
+        self.pop_front()
+    }
+
+    fn peek(&self) -> Option<Ref<'_, T>> {
+        self.peek_front()
+    }
+}
+
+impl<T> Deque<T> for List<T> {
+    fn push_front(&mut self, elem: T) {
+        List::push_front(self, elem)
+    }
+
+    fn push_back(&mut self, elem: T) {
+        List::push_back(self, elem)
+    }
+
+    fn pop_front(&mut self) -> Option<T> {
+        List::pop_front(self)
+    }
+
+    fn pop_back(&mut self) -> Option<T> {
+        List::pop_back(self)
+    }
+
+    fn peek_front(&self) -> Option<Ref<'_, T>> {
+        List::peek_front(self)
+    }
+
+    fn peek_back(&self) -> Option<Ref<'_, T>> {
+        List::peek_back(self)
+    }
+}
+
+// Each node is owned by both of its neighbours (or the list's head/tail), so
+// strong counts are 2 throughout. Nodes that are currently borrowed mutably
+// are labelled as such instead of being read.
+impl<T: fmt::Debug> ToDot for List<T> {
+    fn write_dot(&self, name: &str, graph: &mut Graph) {
+        let root = graph.root(name);
+        if let Some(tail) = &self.tail {
+            graph.edge(&root, &viz::id(Rc::as_ptr(tail)), "label=\"tail\"");
+        }
+        let mut from = root;
+        let mut attrs = "label=\"head\"";
+        let mut cur = self.head.as_ref();
+        while let Some(rc) = cur {
+            let ptr = Rc::as_ptr(rc);
+            // Unguarded so that walking the list doesn't bump any counts
+            let node = unsafe { rc.try_borrow_unguarded() }.ok();
+            let label = match node {
+                Some(node) => viz::rc_label(&node.elem, Rc::strong_count(rc), Rc::weak_count(rc)),
+                None => String::from("<borrowed>"),
+            };
+            graph.node(ptr, &label);
+            graph.edge(&from, &viz::id(ptr), attrs);
+            if let Some(prev) = node.and_then(|node| node.prev.as_ref()) {
+                graph.edge(&viz::id(ptr), &viz::id(Rc::as_ptr(prev)), "style=dashed");
+            }
+            from = viz::id(ptr);
+            attrs = "";
+            cur = node.and_then(|node| node.next.as_ref());
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::List;
+    use crate::test_util::hash_of;
+
+    // Walks every node and checks that no `Ref`/`RefMut` is still holding its
+    // borrow flag.
+    fn borrows_released<T>(list: &List<T>) -> bool {
+        let mut cur = list.head.clone();
+        while let Some(node) = cur {
+            if node.try_borrow_mut().is_err() {
+                return false;
+            }
+            cur = node.borrow().next.clone();
+        }
+        true
+    }
+
+    fn list_from(elems: &[i32]) -> List<i32> {
+        elems.iter().copied().collect()
+    }
+
+    fn to_vec(list: &List<i32>) -> Vec<i32> {
+        list.iter().map(|elem| *elem).collect()
+    }
+
     #[test]
     fn basics() {
         let mut list = List::new();
@@ -241,4 +660,173 @@ mod tests {
         assert_eq!(iter.next_back(), None);
         assert_eq!(iter.next(), None);
     }
+
+    #[test]
+    fn iter() {
+        let list = list_from(&[1, 2, 3, 4]);
+
+        let mut iter = list.iter();
+        assert_eq!(*iter.next().unwrap(), 1);
+        assert_eq!(*iter.next_back().unwrap(), 4);
+        assert_eq!(*iter.next().unwrap(), 2);
+        assert_eq!(*iter.next_back().unwrap(), 3);
+        assert!(iter.next().is_none());
+        assert!(iter.next_back().is_none());
+
+        assert_eq!(
+            list.iter().rev().map(|elem| *elem).collect::<Vec<_>>(),
+            vec![4, 3, 2, 1]
+        );
+        assert!(List::<i32>::new().iter().next().is_none());
+
+        // Guards yielded by the same iterator can be held at the same time
+        let guards: Vec<_> = list.iter().collect();
+        assert_eq!(guards.len(), 4);
+        assert!(list.peek_front().is_some());
+        assert!(!borrows_released(&list));
+        drop(guards);
+        assert!(borrows_released(&list));
+    }
+
+    #[test]
+    fn iter_mut() {
+        let mut list = list_from(&[1, 2, 3]);
+
+        for mut elem in list.iter_mut() {
+            *elem *= 10;
+        }
+        assert_eq!(to_vec(&list), vec![10, 20, 30]);
+
+        let mut iter = list.iter_mut();
+        let mut back = iter.next_back().unwrap();
+        let mut front = iter.next().unwrap();
+        *back += 1;
+        *front += 1;
+        assert_eq!(*iter.next().unwrap(), 20);
+        assert!(iter.next().is_none());
+        assert!(iter.next_back().is_none());
+        drop((front, back));
+
+        assert!(borrows_released(&list));
+        assert_eq!(to_vec(&list), vec![11, 20, 31]);
+    }
+
+    #[test]
+    fn borrow_flags() {
+        let mut list = list_from(&[1, 2, 3]);
+
+        // A partially consumed iterator doesn't keep anything borrowed
+        let mut iter = list.iter();
+        let elem = iter.next().unwrap();
+        assert!(!borrows_released(&list));
+        drop(elem);
+        assert!(borrows_released(&list));
+        assert_eq!(*iter.next().unwrap(), 2);
+
+        let mut iter = list.iter_mut();
+        iter.next_back();
+        iter.next();
+        assert!(iter.next().is_some());
+        assert!(borrows_released(&list));
+
+        *list.peek_front_mut().unwrap() = 5;
+        *list.peek_back_mut().unwrap() = 6;
+
+        let mut cursor = list.cursor();
+        cursor.move_next();
+        let current = cursor.current().unwrap();
+        drop(current);
+        *cursor.current_mut().unwrap() += 1;
+        assert!(borrows_released(&list));
+        assert_eq!(to_vec(&list), vec![6, 2, 6]);
+    }
+
+    #[test]
+    fn cursor() {
+        let mut list = list_from(&[1, 2, 3]);
+        let mut cursor = list.cursor();
+
+        // Starts on the ghost, where "before" is the back and "after" the front
+        assert!(cursor.current().is_none());
+        cursor.insert_before(4);
+        cursor.insert_after(0);
+
+        cursor.move_next();
+        assert_eq!(*cursor.current().unwrap(), 0);
+        cursor.move_next();
+        cursor.move_next();
+        assert_eq!(*cursor.current().unwrap(), 2);
+
+        cursor.insert_before(10);
+        cursor.insert_after(20);
+        assert_eq!(cursor.remove_current(), Some(2));
+        assert_eq!(*cursor.current().unwrap(), 20);
+
+        cursor.move_prev();
+        cursor.move_prev();
+        cursor.move_prev();
+        assert_eq!(cursor.remove_current(), Some(0));
+        assert_eq!(*cursor.current().unwrap(), 1);
+
+        cursor.move_prev();
+        assert!(cursor.current().is_none());
+        assert_eq!(cursor.remove_current(), None);
+        cursor.move_prev();
+        assert_eq!(cursor.remove_current(), Some(4));
+        assert!(cursor.current().is_none());
+
+        assert_eq!(to_vec(&list), vec![1, 10, 20, 3]);
+        assert_eq!(
+            list.iter().rev().map(|elem| *elem).collect::<Vec<_>>(),
+            vec![3, 20, 10, 1]
+        );
+        assert!(borrows_released(&list));
+
+        // Removing everything leaves a usable empty list
+        let mut cursor = list.cursor();
+        cursor.move_next();
+        while cursor.remove_current().is_some() {}
+        assert!(list.peek_front().is_none());
+        assert!(list.peek_back().is_none());
+        list.push_back(7);
+        assert_eq!(list.pop_front(), Some(7));
+    }
+
+    #[test]
+    fn std_traits() {
+        let mut list = list_from(&[1, 2, 3]);
+        list.extend(vec![4, 5]);
+        assert_eq!(format!("{:?}", list), "[1, 2, 3, 4, 5]");
+
+        for mut elem in &mut list {
+            *elem *= 10;
+        }
+        let mut sum = 0;
+        for elem in &list {
+            sum += *elem;
+        }
+        assert_eq!(sum, 150);
+        assert!(borrows_released(&list));
+
+        let copy = list.clone();
+        assert_eq!(copy, list);
+        assert_eq!(list, list);
+        assert_eq!(hash_of(&copy), hash_of(&list));
+        assert_eq!(List::<i32>::default(), List::new());
+        assert!(borrows_released(&list));
+
+        assert!(list < list_from(&[10, 20, 31]));
+        assert!(list > list_from(&[10, 20, 30]));
+        assert!(list_from(&[10, 20]) < list_from(&[10, 20, 30]));
+        assert_eq!(list.cmp(&copy), std::cmp::Ordering::Equal);
+        assert_ne!(list, list_from(&[10, 20, 30]));
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn deep_clone_and_drop() {
+        let list: List<i32> = (0..1_000_000).collect();
+        let copy = list.clone();
+        assert!(copy == list);
+    }
 }
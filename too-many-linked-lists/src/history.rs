@@ -0,0 +1,470 @@
+use alloc::collections::BTreeMap;
+use alloc::rc::Rc;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::third::List;
+
+// Versions of the state form a tree, and each line of history through it is
+// a `third::List` running from one version back to the oldest, so a commit
+// is a single `prepend` and lines share everything they have in common.
+//
+// `current` is the line we're on. Undoing walks back along it and remembers
+// where it came from on `redo`. Committing after an undo starts a new line;
+// the old one is kept as a branch rather than thrown away, and can be
+// checked out again later.
+pub struct History<S> {
+    current: Line<S>,
+    redo: List<Line<S>>,
+    branches: Vec<Line<S>>,
+    checkpoints: Vec<(String, Line<S>)>,
+    max_depth: Option<usize>,
+    next_seq: u64,
+    compacted_at: u64,
+}
+
+type Line<S> = List<Rc<Version<S>>>;
+
+// `seq` numbers every version in commit order, across all branches.
+struct Version<S> {
+    state: S,
+    seq: u64,
+}
+
+impl<S> History<S> {
+    pub fn new(initial: S) -> Self {
+        History {
+            current: List::new().prepend(Rc::new(Version {
+                state: initial,
+                seq: 0,
+            })),
+            redo: List::new(),
+            branches: Vec::new(),
+            checkpoints: Vec::new(),
+            max_depth: None,
+            next_seq: 1,
+            compacted_at: 0,
+        }
+    }
+
+    // Only the `max_depth` most recent commits, on whichever branch, can be
+    // gone back to; anything older is evicted, along with branches and
+    // checkpoints that lead to it. Evicted versions are freed in batches, so
+    // at most about twice the bound is ever kept in memory.
+    pub fn with_max_depth(initial: S, max_depth: usize) -> Self {
+        History {
+            max_depth: Some(max_depth),
+            ..History::new(initial)
+        }
+    }
+
+    pub fn current(&self) -> &S {
+        &self.current.head().unwrap().state
+    }
+
+    // Drops everything that could have been redone, keeping it as a branch.
+    pub fn commit(&mut self, state: S) {
+        if let Some(tip) = self.redo.iter().last() {
+            self.branches.push(tip.clone());
+        }
+        self.redo = List::new();
+        self.current = self.current.prepend(Rc::new(Version {
+            state,
+            seq: self.next_seq,
+        }));
+        self.next_seq += 1;
+
+        if let Some(max_depth) = self.max_depth {
+            let oldest = self.oldest();
+            let live = |line: &Line<S>| line.head().is_some_and(|v| v.seq >= oldest);
+            self.branches.retain(live);
+            self.checkpoints.retain(|(_, line)| live(line));
+            if self.next_seq - self.compacted_at > max_depth as u64 + 1 {
+                self.compact();
+            }
+        }
+    }
+
+    pub fn can_undo(&self) -> bool {
+        let oldest = self.oldest();
+        self.current.tail().head().is_some_and(|v| v.seq >= oldest)
+    }
+
+    pub fn undo(&mut self) -> Option<&S> {
+        if !self.can_undo() {
+            return None;
+        }
+        self.redo = self.redo.prepend(self.current.clone());
+        self.current = self.current.tail();
+        Some(self.current())
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    pub fn redo(&mut self) -> Option<&S> {
+        let next = self.redo.head()?.clone();
+        self.redo = self.redo.tail();
+        self.current = next;
+        Some(self.current())
+    }
+
+    // The current state, then every earlier one that `undo` can reach.
+    pub fn past(&self) -> impl Iterator<Item = &S> {
+        let oldest = self.oldest();
+        self.current
+            .iter()
+            .take_while(move |v| v.seq >= oldest)
+            .map(|v| &v.state)
+    }
+
+    // The newest state on every line that was left behind, oldest first.
+    pub fn branches(&self) -> impl Iterator<Item = &S> {
+        self.branches.iter().map(|line| &line.head().unwrap().state)
+    }
+
+    // Switches to the newest state of a branch. The line we were on becomes
+    // a branch in turn.
+    pub fn checkout(&mut self, branch: usize) -> Option<&S> {
+        if branch >= self.branches.len() {
+            return None;
+        }
+        self.jump(self.branches[branch].clone());
+        Some(self.current())
+    }
+
+    // Labels the current state. Reusing a label moves it.
+    pub fn checkpoint(&mut self, label: impl Into<String>) {
+        let label = label.into();
+        self.checkpoints.retain(|(l, _)| *l != label);
+        self.checkpoints.push((label, self.current.clone()));
+    }
+
+    pub fn checkpoints(&self) -> impl Iterator<Item = (&str, &S)> {
+        self.checkpoints
+            .iter()
+            .map(|(label, line)| (label.as_str(), &line.head().unwrap().state))
+    }
+
+    pub fn restore(&mut self, label: &str) -> Option<&S> {
+        let (_, target) = self.checkpoints.iter().find(|(l, _)| l == label)?;
+        self.jump(target.clone());
+        Some(self.current())
+    }
+
+    fn oldest(&self) -> u64 {
+        match self.max_depth {
+            Some(max_depth) => self.next_seq.saturating_sub(max_depth as u64 + 1),
+            None => 0,
+        }
+    }
+
+    // If `target` lies behind the newest state of the current line, this is
+    // just a run of undos and everything after it can still be redone.
+    // Otherwise we move onto the branch it lies on, the same way, and the
+    // line we leave becomes a branch.
+    fn jump(&mut self, target: Line<S>) {
+        let tip = self.redo.iter().last().unwrap_or(&self.current).clone();
+
+        if let Some(redo) = path_to(&tip, &target) {
+            self.redo = redo;
+        } else {
+            let found = self
+                .branches
+                .iter()
+                .enumerate()
+                .find_map(|(i, branch)| path_to(branch, &target).map(|redo| (i, redo)));
+            self.redo = match found {
+                Some((i, redo)) => {
+                    self.branches.remove(i);
+                    redo
+                }
+                None => List::new(),
+            };
+            self.branches.push(tip);
+        }
+        self.current = target;
+    }
+
+    // Evicted versions stay reachable from the older parts of each line until
+    // those lines are rebuilt without them. Every version is rebuilt once, so
+    // lines that shared it before still share it afterwards.
+    fn compact(&mut self) {
+        let oldest = self.oldest();
+        let mut rebuilt = BTreeMap::new();
+
+        self.current = truncate(&self.current, oldest, &mut rebuilt);
+        self.redo = self
+            .redo
+            .iter()
+            .map(|line| truncate(line, oldest, &mut rebuilt))
+            .collect();
+        for line in &mut self.branches {
+            *line = truncate(line, oldest, &mut rebuilt);
+        }
+        for (_, line) in &mut self.checkpoints {
+            *line = truncate(line, oldest, &mut rebuilt);
+        }
+        self.compacted_at = self.next_seq;
+    }
+}
+
+// The redo stack that leads from `target` back up to the newest state of
+// `line`, if `target` is on it.
+fn path_to<S>(line: &Line<S>, target: &Line<S>) -> Option<List<Line<S>>> {
+    let mut path = Vec::new();
+    let mut cur = line.clone();
+    while !cur.ptr_eq(target) {
+        cur.head()?;
+        path.push(cur.clone());
+        cur = cur.tail();
+    }
+    Some(path.into_iter().rev().collect())
+}
+
+// Copies the part of `line` that isn't older than `oldest`, reusing any
+// version's new spine that an earlier call already built.
+fn truncate<S>(
+    line: &Line<S>,
+    oldest: u64,
+    rebuilt: &mut BTreeMap<*const Version<S>, Line<S>>,
+) -> Line<S> {
+    let mut kept = Vec::new();
+    let mut cur = line.clone();
+    let mut spine = List::new();
+    while let Some(version) = cur.head() {
+        if version.seq < oldest {
+            break;
+        }
+        if let Some(done) = rebuilt.get(&Rc::as_ptr(version)) {
+            spine = done.clone();
+            break;
+        }
+        kept.push(version.clone());
+        cur = cur.tail();
+    }
+
+    for version in kept.into_iter().rev() {
+        let ptr = Rc::as_ptr(&version);
+        spine = spine.prepend(version);
+        rebuilt.insert(ptr, spine.clone());
+    }
+    spine
+}
+
+#[cfg(test)]
+mod tests {
+    use super::History;
+    use crate::third::List;
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    fn past<S: Clone>(history: &History<S>) -> Vec<S> {
+        history.past().cloned().collect()
+    }
+
+    fn branches<S: Clone>(history: &History<S>) -> Vec<S> {
+        history.branches().cloned().collect()
+    }
+
+    #[test]
+    fn undo_redo() {
+        let mut history = History::new(0);
+        assert!(!history.can_undo());
+        assert_eq!(history.undo(), None);
+
+        for i in 1..=3 {
+            history.commit(i);
+        }
+        assert_eq!(history.undo(), Some(&2));
+        assert_eq!(history.undo(), Some(&1));
+        assert_eq!(history.redo(), Some(&2));
+        assert!(history.can_redo());
+        assert_eq!(past(&history), vec![2, 1, 0]);
+
+        history.undo();
+        history.undo();
+        history.undo();
+        assert_eq!(history.current(), &0);
+        assert_eq!(history.undo(), None);
+        for expected in 1..=3 {
+            assert_eq!(history.redo(), Some(&expected));
+        }
+        assert_eq!(history.redo(), None);
+    }
+
+    #[test]
+    fn commits_after_undo_branch_off() {
+        let mut history = History::new("a");
+        history.commit("b");
+        history.commit("c");
+        history.undo();
+        history.undo();
+
+        history.commit("d");
+        assert!(!history.can_redo());
+        assert_eq!(past(&history), vec!["d", "a"]);
+        assert_eq!(branches(&history), vec!["c"]);
+
+        // Checking out swaps the line we're on for the branch
+        assert_eq!(history.checkout(0), Some(&"c"));
+        assert_eq!(branches(&history), vec!["d"]);
+        assert_eq!(past(&history), vec!["c", "b", "a"]);
+        assert_eq!(history.checkout(1), None);
+
+        history.undo();
+        history.commit("e");
+        assert_eq!(branches(&history), vec!["d", "c"]);
+        assert_eq!(past(&history), vec!["e", "b", "a"]);
+    }
+
+    #[test]
+    fn checkpoints() {
+        let mut history = History::new(0);
+        history.commit(1);
+        history.checkpoint("saved");
+        history.commit(2);
+        history.commit(3);
+
+        // Restoring along the current line keeps the way back
+        assert_eq!(history.restore("saved"), Some(&1));
+        assert!(branches(&history).is_empty());
+        assert_eq!(history.redo(), Some(&2));
+        assert_eq!(history.restore("saved"), Some(&1));
+
+        history.commit(10);
+        history.checkpoint("other");
+        assert_eq!(branches(&history), vec![3]);
+
+        // Restoring onto another line switches to it, leaving ours as a
+        // branch, and everything after the checkpoint can still be redone
+        assert_eq!(history.checkout(0), Some(&3));
+        assert_eq!(branches(&history), vec![10]);
+        assert_eq!(history.restore("saved"), Some(&1));
+        assert_eq!(history.redo(), Some(&2));
+        history.undo();
+        assert_eq!(history.restore("other"), Some(&10));
+        assert_eq!(branches(&history), vec![3]);
+        history.commit(11);
+        assert_eq!(history.restore("saved"), Some(&1));
+        assert_eq!(history.redo(), Some(&10));
+        assert_eq!(history.redo(), Some(&11));
+
+        history.checkpoint("saved");
+        assert_eq!(
+            history.checkpoints().collect::<Vec<_>>(),
+            vec![("other", &10), ("saved", &11)]
+        );
+        assert_eq!(history.restore("missing"), None);
+    }
+
+    #[test]
+    fn bounded_depth() {
+        let mut history = History::with_max_depth(0, 3);
+        for i in 1..=10 {
+            history.commit(i);
+        }
+        assert_eq!(past(&history), vec![10, 9, 8, 7]);
+        for _ in 0..3 {
+            assert!(history.undo().is_some());
+        }
+        assert_eq!(history.undo(), None);
+        assert_eq!(history.current(), &7);
+
+        // Branches and checkpoints go once their newest state is evicted
+        history.checkpoint("seven");
+        history.commit(11);
+        assert_eq!(branches(&history), vec![10]);
+        assert_eq!(history.checkpoints().count(), 0);
+        history.checkpoint("eleven");
+        history.commit(12);
+        history.commit(13);
+        assert_eq!(branches(&history), vec![10]);
+        history.commit(14);
+        assert!(branches(&history).is_empty());
+        assert_eq!(history.checkpoints().count(), 1);
+        history.commit(15);
+        assert_eq!(history.checkpoints().count(), 0);
+        assert_eq!(past(&history), vec![15, 14, 13, 12]);
+
+        let mut history = History::with_max_depth(0, 0);
+        history.commit(1);
+        assert!(!history.can_undo());
+    }
+
+    // Counts how many are alive, so tests can see what the history holds on to.
+    struct Tracked(Rc<Cell<usize>>);
+
+    impl Tracked {
+        fn new(live: &Rc<Cell<usize>>) -> Self {
+            live.set(live.get() + 1);
+            Tracked(live.clone())
+        }
+    }
+
+    impl Drop for Tracked {
+        fn drop(&mut self) {
+            self.0.set(self.0.get() - 1);
+        }
+    }
+
+    #[test]
+    fn memory_is_proportional_to_changes() {
+        let live = Rc::new(Cell::new(0));
+        let mut history = History::new(Tracked::new(&live));
+        for _ in 0..1_000 {
+            history.commit(Tracked::new(&live));
+        }
+        assert_eq!(live.get(), 1_001);
+
+        // Branching off keeps both lines, but only adds the new commits
+        for _ in 0..500 {
+            history.undo();
+        }
+        for _ in 0..100 {
+            history.commit(Tracked::new(&live));
+        }
+        assert_eq!(live.get(), 1_101);
+        history.checkout(0);
+        history.checkpoint("tip");
+        assert_eq!(live.get(), 1_101);
+
+        drop(history);
+        assert_eq!(live.get(), 0);
+    }
+
+    #[test]
+    fn bounded_memory() {
+        let live = Rc::new(Cell::new(0));
+        let mut history = History::with_max_depth(Tracked::new(&live), 10);
+        for i in 0..10_000 {
+            history.commit(Tracked::new(&live));
+            if i % 7 == 0 {
+                history.undo();
+                history.undo();
+            }
+            if i % 50 == 0 {
+                history.checkpoint("latest");
+            }
+            assert!(live.get() <= 2 * 11 + 1, "{} states alive", live.get());
+        }
+    }
+
+    #[test]
+    fn states_share_structure() {
+        // A document that grows by a line per commit: every version shares
+        // all earlier lines, so the history holds one copy of each.
+        let live = Rc::new(Cell::new(0));
+        let mut history = History::new(List::new());
+        for _ in 0..1_000 {
+            let doc = history.current().prepend(Tracked::new(&live));
+            history.commit(doc);
+        }
+        assert_eq!(live.get(), 1_000);
+        assert_eq!(
+            history.past().map(List::len).sum::<usize>(),
+            1_000 * 1_001 / 2
+        );
+    }
+}
@@ -0,0 +1,488 @@
+use core::cell::Cell;
+use core::marker::{PhantomData, PhantomPinned};
+use core::pin::Pin;
+use core::ptr::NonNull;
+use core::sync::atomic::{AtomicU64, Ordering};
+
+// Every list gets its own id so elements know which list (if any) they're
+// linked into, without the list needing a stable address.
+static NEXT_LIST_ID: AtomicU64 = AtomicU64::new(1);
+
+// Embedded in the elements themselves. It only ever points at other elements
+// of the same list, type-erased here and recovered by `List<A>`.
+pub struct Links {
+    owner: Cell<u64>,
+    prev: Cell<Option<NonNull<()>>>,
+    next: Cell<Option<NonNull<()>>>,
+    _pin: PhantomPinned,
+}
+
+impl Links {
+    pub const fn new() -> Self {
+        Links {
+            owner: Cell::new(0),
+            prev: Cell::new(None),
+            next: Cell::new(None),
+            _pin: PhantomPinned,
+        }
+    }
+
+    pub fn is_linked(&self) -> bool {
+        self.owner.get() != 0
+    }
+}
+
+impl Default for Links {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Maps an element to the `Links` embedded in it.
+///
+/// # Safety
+///
+/// `links` must always return the same `Links` field of `elem`, and that
+/// field must not be shared with any other adapter.
+pub unsafe trait Adapter {
+    type Elem;
+
+    fn links(elem: &Self::Elem) -> &Links;
+}
+
+// The list only borrows its elements, for `'a`, so they can neither move nor
+// be dropped while they're linked. Nothing is allocated by the list itself.
+pub struct List<'a, A: Adapter> {
+    id: u64,
+    head: Option<NonNull<A::Elem>>,
+    tail: Option<NonNull<A::Elem>>,
+    len: usize,
+    _boo: PhantomData<Pin<&'a A::Elem>>,
+}
+
+pub struct Iter<'l, 'a, A: Adapter> {
+    front: Option<NonNull<A::Elem>>,
+    back: Option<NonNull<A::Elem>>,
+    len: usize,
+    _boo: PhantomData<&'l List<'a, A>>,
+}
+
+impl<'a, A: Adapter> Iterator for Iter<'_, 'a, A> {
+    type Item = Pin<&'a A::Elem>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.len == 0 {
+            return None;
+        }
+
+        self.front.map(|elem| unsafe {
+            self.len -= 1;
+            self.front = links_of::<A>(elem).next.get().map(NonNull::cast);
+            Pin::new_unchecked(elem.as_ref())
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<A: Adapter> DoubleEndedIterator for Iter<'_, '_, A> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.len == 0 {
+            return None;
+        }
+
+        self.back.map(|elem| unsafe {
+            self.len -= 1;
+            self.back = links_of::<A>(elem).prev.get().map(NonNull::cast);
+            Pin::new_unchecked(elem.as_ref())
+        })
+    }
+}
+
+impl<A: Adapter> ExactSizeIterator for Iter<'_, '_, A> {}
+
+// Safety: `elem` must point at an element borrowed by a live list.
+unsafe fn links_of<'e, A: Adapter>(elem: NonNull<A::Elem>) -> &'e Links
+where
+    A::Elem: 'e,
+{
+    A::links(elem.as_ref())
+}
+
+impl<A: Adapter> Default for List<'_, A> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a, A: Adapter> List<'a, A> {
+    pub fn new() -> Self {
+        List {
+            id: NEXT_LIST_ID.fetch_add(1, Ordering::Relaxed),
+            head: None,
+            tail: None,
+            len: 0,
+            _boo: PhantomData,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn contains(&self, elem: &A::Elem) -> bool {
+        A::links(elem).owner.get() == self.id
+    }
+
+    pub fn front(&self) -> Option<Pin<&'a A::Elem>> {
+        self.head
+            .map(|elem| unsafe { Pin::new_unchecked(elem.as_ref()) })
+    }
+
+    pub fn back(&self) -> Option<Pin<&'a A::Elem>> {
+        self.tail
+            .map(|elem| unsafe { Pin::new_unchecked(elem.as_ref()) })
+    }
+
+    pub fn iter(&self) -> Iter<'_, 'a, A> {
+        Iter {
+            front: self.head,
+            back: self.tail,
+            len: self.len,
+            _boo: PhantomData,
+        }
+    }
+
+    // Panics if `elem` is already linked into a list.
+    pub fn push_front(&mut self, elem: Pin<&'a A::Elem>) {
+        let links = self.claim(&elem);
+        let elem = NonNull::from(elem.get_ref());
+
+        links.next.set(self.head.map(NonNull::cast));
+        match self.head {
+            Some(old) => unsafe { links_of::<A>(old).prev.set(Some(elem.cast())) },
+            None => self.tail = Some(elem),
+        }
+        self.head = Some(elem);
+        self.len += 1;
+    }
+
+    // Panics if `elem` is already linked into a list.
+    pub fn push_back(&mut self, elem: Pin<&'a A::Elem>) {
+        let links = self.claim(&elem);
+        let elem = NonNull::from(elem.get_ref());
+
+        links.prev.set(self.tail.map(NonNull::cast));
+        match self.tail {
+            Some(old) => unsafe { links_of::<A>(old).next.set(Some(elem.cast())) },
+            None => self.head = Some(elem),
+        }
+        self.tail = Some(elem);
+        self.len += 1;
+    }
+
+    pub fn pop_front(&mut self) -> Option<Pin<&'a A::Elem>> {
+        self.head.map(|elem| unsafe {
+            self.unlink(elem);
+            Pin::new_unchecked(elem.as_ref())
+        })
+    }
+
+    pub fn pop_back(&mut self) -> Option<Pin<&'a A::Elem>> {
+        self.tail.map(|elem| unsafe {
+            self.unlink(elem);
+            Pin::new_unchecked(elem.as_ref())
+        })
+    }
+
+    // Returns false (and does nothing) if `elem` isn't linked into this list.
+    pub fn remove(&mut self, elem: Pin<&'a A::Elem>) -> bool {
+        if !self.contains(&elem) {
+            return false;
+        }
+
+        unsafe { self.unlink(NonNull::from(elem.get_ref())) };
+        true
+    }
+
+    pub fn clear(&mut self) {
+        while self.pop_front().is_some() {}
+    }
+
+    fn claim<'e>(&self, elem: &'e A::Elem) -> &'e Links {
+        let links = A::links(elem);
+        assert!(!links.is_linked(), "element is already linked into a list");
+        links.owner.set(self.id);
+        links
+    }
+
+    // Safety: `elem` must be linked into this list.
+    unsafe fn unlink(&mut self, elem: NonNull<A::Elem>) {
+        let links = links_of::<A>(elem);
+        let prev = links.prev.take().map(NonNull::cast::<A::Elem>);
+        let next = links.next.take().map(NonNull::cast::<A::Elem>);
+        links.owner.set(0);
+
+        match prev {
+            Some(prev) => links_of::<A>(prev).next.set(next.map(NonNull::cast)),
+            None => self.head = next,
+        }
+        match next {
+            Some(next) => links_of::<A>(next).prev.set(prev.map(NonNull::cast)),
+            None => self.tail = prev,
+        }
+        self.len -= 1;
+    }
+}
+
+// Unlink whatever is left so the elements can be reused once the list is gone.
+impl<A: Adapter> Drop for List<'_, A> {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+impl<'l, 'a, A: Adapter> IntoIterator for &'l List<'a, A> {
+    type Item = Pin<&'a A::Elem>;
+    type IntoIter = Iter<'l, 'a, A>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Adapter, Links, List};
+    use std::cell::Cell;
+    use std::pin::pin;
+
+    struct Timer {
+        deadline: u32,
+        fired: Cell<bool>,
+        links: Links,
+    }
+
+    impl Timer {
+        fn new(deadline: u32) -> Self {
+            Timer {
+                deadline,
+                fired: Cell::new(false),
+                links: Links::new(),
+            }
+        }
+    }
+
+    struct TimerAdapter;
+
+    unsafe impl Adapter for TimerAdapter {
+        type Elem = Timer;
+
+        fn links(elem: &Timer) -> &Links {
+            &elem.links
+        }
+    }
+
+    // A second set of links, so the same element can sit in two lists.
+    struct Waiter {
+        id: u32,
+        by_id: Links,
+        by_priority: Links,
+    }
+
+    struct ById;
+    struct ByPriority;
+
+    unsafe impl Adapter for ById {
+        type Elem = Waiter;
+
+        fn links(elem: &Waiter) -> &Links {
+            &elem.by_id
+        }
+    }
+
+    unsafe impl Adapter for ByPriority {
+        type Elem = Waiter;
+
+        fn links(elem: &Waiter) -> &Links {
+            &elem.by_priority
+        }
+    }
+
+    fn deadlines(list: &List<TimerAdapter>) -> Vec<u32> {
+        let forward: Vec<_> = list.iter().map(|timer| timer.deadline).collect();
+        let mut backward: Vec<_> = list.iter().rev().map(|timer| timer.deadline).collect();
+        backward.reverse();
+        assert_eq!(forward, backward);
+        assert_eq!(forward.len(), list.len());
+        forward
+    }
+
+    #[test]
+    fn basics() {
+        let a = pin!(Timer::new(1));
+        let b = pin!(Timer::new(2));
+        let c = pin!(Timer::new(3));
+
+        let mut list = List::<TimerAdapter>::new();
+        assert!(list.is_empty());
+        assert!(list.pop_front().is_none());
+
+        list.push_back(b.as_ref());
+        list.push_front(a.as_ref());
+        list.push_back(c.as_ref());
+        assert_eq!(deadlines(&list), vec![1, 2, 3]);
+        assert_eq!(list.front().unwrap().deadline, 1);
+        assert_eq!(list.back().unwrap().deadline, 3);
+        assert!(b.links.is_linked());
+
+        assert_eq!(list.pop_front().unwrap().deadline, 1);
+        assert_eq!(list.pop_back().unwrap().deadline, 3);
+        assert!(!a.links.is_linked());
+        assert_eq!(deadlines(&list), vec![2]);
+
+        // Popped elements can go right back in
+        list.push_front(c.as_ref());
+        assert_eq!(deadlines(&list), vec![3, 2]);
+    }
+
+    #[test]
+    fn remove() {
+        let timers = [
+            pin!(Timer::new(1)),
+            pin!(Timer::new(2)),
+            pin!(Timer::new(3)),
+            pin!(Timer::new(4)),
+        ];
+
+        let mut list = List::<TimerAdapter>::new();
+        for timer in &timers {
+            list.push_back(timer.as_ref());
+        }
+
+        assert!(list.remove(timers[1].as_ref()));
+        assert!(!list.remove(timers[1].as_ref()));
+        assert_eq!(deadlines(&list), vec![1, 3, 4]);
+
+        assert!(list.remove(timers[0].as_ref()));
+        assert!(list.remove(timers[3].as_ref()));
+        assert_eq!(deadlines(&list), vec![3]);
+        assert_eq!(list.front().unwrap().deadline, 3);
+        assert_eq!(list.back().unwrap().deadline, 3);
+
+        assert!(list.remove(timers[2].as_ref()));
+        assert!(list.is_empty());
+        assert!(timers.iter().all(|timer| !timer.links.is_linked()));
+    }
+
+    #[test]
+    fn remove_from_other_list() {
+        let a = pin!(Timer::new(1));
+        let b = pin!(Timer::new(2));
+
+        let mut first = List::<TimerAdapter>::new();
+        let mut second = List::<TimerAdapter>::new();
+        first.push_back(a.as_ref());
+        second.push_back(b.as_ref());
+
+        assert!(!first.contains(&b));
+        assert!(!first.remove(b.as_ref()));
+        assert_eq!(deadlines(&second), vec![2]);
+        assert!(second.remove(b.as_ref()));
+        assert!(first.contains(&a));
+    }
+
+    #[test]
+    #[should_panic(expected = "already linked")]
+    fn push_linked() {
+        let a = pin!(Timer::new(1));
+
+        let mut first = List::<TimerAdapter>::new();
+        let mut second = List::<TimerAdapter>::new();
+        first.push_back(a.as_ref());
+        second.push_back(a.as_ref());
+    }
+
+    #[test]
+    fn drop_unlinks() {
+        let a = pin!(Timer::new(1));
+        let b = pin!(Timer::new(2));
+
+        {
+            let mut list = List::<TimerAdapter>::new();
+            list.push_back(a.as_ref());
+            list.push_back(b.as_ref());
+        }
+        assert!(!a.links.is_linked());
+        assert!(!b.links.is_linked());
+
+        let mut list = List::<TimerAdapter>::new();
+        list.push_back(b.as_ref());
+        list.push_back(a.as_ref());
+        assert_eq!(deadlines(&list), vec![2, 1]);
+    }
+
+    #[test]
+    fn fire_expired() {
+        let timers = [
+            pin!(Timer::new(5)),
+            pin!(Timer::new(10)),
+            pin!(Timer::new(15)),
+        ];
+
+        let mut list = List::<TimerAdapter>::new();
+        for timer in &timers {
+            list.push_back(timer.as_ref());
+        }
+
+        while let Some(timer) = list.front() {
+            if timer.deadline > 10 {
+                break;
+            }
+            list.pop_front();
+            timer.fired.set(true);
+        }
+
+        let fired: Vec<_> = timers.iter().map(|timer| timer.fired.get()).collect();
+        assert_eq!(fired, vec![true, true, false]);
+        assert_eq!(deadlines(&list), vec![15]);
+    }
+
+    #[test]
+    fn multiple_adapters() {
+        let waiters = [
+            pin!(Waiter {
+                id: 1,
+                by_id: Links::new(),
+                by_priority: Links::new(),
+            }),
+            pin!(Waiter {
+                id: 2,
+                by_id: Links::new(),
+                by_priority: Links::new(),
+            }),
+        ];
+
+        let mut by_id = List::<ById>::new();
+        let mut by_priority = List::<ByPriority>::new();
+        for waiter in &waiters {
+            by_id.push_back(waiter.as_ref());
+            by_priority.push_front(waiter.as_ref());
+        }
+
+        let ids: Vec<_> = by_id.iter().map(|waiter| waiter.id).collect();
+        assert_eq!(ids, vec![1, 2]);
+        let ids: Vec<_> = by_priority.iter().map(|waiter| waiter.id).collect();
+        assert_eq!(ids, vec![2, 1]);
+
+        by_id.remove(waiters[0].as_ref());
+        assert!(by_priority.contains(&waiters[0]));
+        assert_eq!(by_priority.len(), 2);
+    }
+}
@@ -1,4 +1,57 @@
+// Only `alloc` is needed unless the `std` feature (on by default) is enabled,
+// which adds the structures built on threads, locks and hashing.
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+#![feature(allocator_api)]
+
+extern crate alloc;
+
+pub mod codec;
+#[cfg(feature = "std")]
+pub mod concurrent;
+pub mod fifth;
 pub mod first;
 pub mod fourth;
+pub mod history;
+pub mod intrusive;
+#[cfg(feature = "std")]
+pub mod linked_hash;
+#[cfg(feature = "std")]
+pub mod lru;
+pub mod pool;
 pub mod second;
+pub mod sixth;
+pub mod skiplist;
+pub mod slab_list;
+pub mod stream;
 pub mod third;
+pub mod traits;
+pub mod unrolled;
+pub mod viz;
+
+#[cfg(test)]
+pub(crate) mod test_util {
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::{Hash, Hasher};
+
+    pub(crate) fn hash_of<T: Hash>(value: &T) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        value.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    // A tiny deterministic RNG for the randomized tests.
+    pub(crate) struct XorShift(pub(crate) u64);
+
+    impl XorShift {
+        pub(crate) fn next<N>(&mut self, bound: N) -> N
+        where
+            N: TryFrom<u64> + TryInto<u64>,
+        {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            let bound = bound.try_into().ok().unwrap();
+            N::try_from(self.0 % bound).ok().unwrap()
+        }
+    }
+}
@@ -0,0 +1,542 @@
+use std::borrow::Borrow;
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::Hash;
+
+use crate::slab_list::{self, Handle};
+
+// Built like `LruCache`: the entries live in a `slab_list` in iteration
+// order, and the map points every key at its entry's handle, so lookups,
+// removals and moves are all O(1).
+//
+// Inserting a key that's already there replaces its value but keeps its
+// place; use `move_to_back` to make it the newest.
+pub struct LinkedHashMap<K, V> {
+    map: HashMap<K, Handle>,
+    list: slab_list::List<(K, V)>,
+}
+
+pub struct Iter<'a, K, V> {
+    inner: slab_list::Iter<'a, (K, V)>,
+}
+
+pub struct IntoIter<K, V> {
+    list: slab_list::List<(K, V)>,
+}
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|(k, v)| (k, v))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<K, V> DoubleEndedIterator for Iter<'_, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back().map(|(k, v)| (k, v))
+    }
+}
+
+impl<K, V> ExactSizeIterator for Iter<'_, K, V> {}
+
+impl<K, V> Iterator for IntoIter<K, V> {
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.list.pop_front()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.list.len(), Some(self.list.len()))
+    }
+}
+
+impl<K, V> DoubleEndedIterator for IntoIter<K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.list.pop_back()
+    }
+}
+
+impl<K, V> ExactSizeIterator for IntoIter<K, V> {}
+
+impl<K: Hash + Eq + Clone, V> Default for LinkedHashMap<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Hash + Eq + Clone, V> LinkedHashMap<K, V> {
+    pub fn new() -> Self {
+        LinkedHashMap {
+            map: HashMap::new(),
+            list: slab_list::List::new(),
+        }
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        LinkedHashMap {
+            map: HashMap::with_capacity(capacity),
+            list: slab_list::List::with_capacity(capacity),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.list.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.list.is_empty()
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.map.contains_key(key)
+    }
+
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let handle = *self.map.get(key)?;
+        self.list.get(handle).map(|(_, v)| v)
+    }
+
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let handle = *self.map.get(key)?;
+        self.list.get_mut(handle).map(|(_, v)| v)
+    }
+
+    // New keys go at the back. Returns the previous value if `key` was
+    // already there.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        if let Some(&handle) = self.map.get(&key) {
+            let (_, old) = self.list.get_mut(handle)?;
+            return Some(std::mem::replace(old, value));
+        }
+
+        let handle = self.list.push_back((key.clone(), value));
+        self.map.insert(key, handle);
+        None
+    }
+
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.remove_entry(key).map(|(_, v)| v)
+    }
+
+    pub fn remove_entry<Q>(&mut self, key: &Q) -> Option<(K, V)>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let handle = self.map.remove(key)?;
+        self.list.remove(handle)
+    }
+
+    // Returns whether `key` was there to move.
+    pub fn move_to_front<Q>(&mut self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.map
+            .get(key)
+            .and_then(|&handle| self.list.move_to_front(handle))
+            .is_some()
+    }
+
+    pub fn move_to_back<Q>(&mut self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.map
+            .get(key)
+            .and_then(|&handle| self.list.move_to_back(handle))
+            .is_some()
+    }
+
+    pub fn front(&self) -> Option<(&K, &V)> {
+        self.list.front().map(|(k, v)| (k, v))
+    }
+
+    pub fn back(&self) -> Option<(&K, &V)> {
+        self.list.back().map(|(k, v)| (k, v))
+    }
+
+    pub fn pop_front(&mut self) -> Option<(K, V)> {
+        let (key, value) = self.list.pop_front()?;
+        self.map.remove(&key);
+        Some((key, value))
+    }
+
+    pub fn pop_back(&mut self) -> Option<(K, V)> {
+        let (key, value) = self.list.pop_back()?;
+        self.map.remove(&key);
+        Some((key, value))
+    }
+
+    pub fn clear(&mut self) {
+        self.map.clear();
+        self.list.clear();
+    }
+
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        Iter {
+            inner: self.list.iter(),
+        }
+    }
+
+    pub fn keys(&self) -> impl DoubleEndedIterator<Item = &K> {
+        self.iter().map(|(k, _)| k)
+    }
+
+    pub fn values(&self) -> impl DoubleEndedIterator<Item = &V> {
+        self.iter().map(|(_, v)| v)
+    }
+}
+
+impl<K: Hash + Eq + Clone, V> Extend<(K, V)> for LinkedHashMap<K, V> {
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        for (key, value) in iter {
+            self.insert(key, value);
+        }
+    }
+}
+
+impl<K: Hash + Eq + Clone, V> FromIterator<(K, V)> for LinkedHashMap<K, V> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut map = Self::new();
+        map.extend(iter);
+        map
+    }
+}
+
+impl<K, V> IntoIterator for LinkedHashMap<K, V> {
+    type Item = (K, V);
+    type IntoIter = IntoIter<K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter { list: self.list }
+    }
+}
+
+impl<'a, K: Hash + Eq + Clone, V> IntoIterator for &'a LinkedHashMap<K, V> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<K: Hash + Eq + Clone + fmt::Debug, V: fmt::Debug> fmt::Debug for LinkedHashMap<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+// A `LinkedHashMap` with nothing but keys.
+pub struct LinkedHashSet<T> {
+    map: LinkedHashMap<T, ()>,
+}
+
+impl<T: Hash + Eq + Clone> Default for LinkedHashSet<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Hash + Eq + Clone> LinkedHashSet<T> {
+    pub fn new() -> Self {
+        LinkedHashSet {
+            map: LinkedHashMap::new(),
+        }
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        LinkedHashSet {
+            map: LinkedHashMap::with_capacity(capacity),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    pub fn contains<Q>(&self, value: &Q) -> bool
+    where
+        T: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.map.contains_key(value)
+    }
+
+    // Returns whether `value` was new. One that was already there keeps its
+    // place.
+    pub fn insert(&mut self, value: T) -> bool {
+        self.map.insert(value, ()).is_none()
+    }
+
+    pub fn remove<Q>(&mut self, value: &Q) -> bool
+    where
+        T: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.map.remove(value).is_some()
+    }
+
+    pub fn move_to_front<Q>(&mut self, value: &Q) -> bool
+    where
+        T: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.map.move_to_front(value)
+    }
+
+    pub fn move_to_back<Q>(&mut self, value: &Q) -> bool
+    where
+        T: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.map.move_to_back(value)
+    }
+
+    pub fn front(&self) -> Option<&T> {
+        self.map.front().map(|(k, _)| k)
+    }
+
+    pub fn back(&self) -> Option<&T> {
+        self.map.back().map(|(k, _)| k)
+    }
+
+    pub fn pop_front(&mut self) -> Option<T> {
+        self.map.pop_front().map(|(k, _)| k)
+    }
+
+    pub fn pop_back(&mut self) -> Option<T> {
+        self.map.pop_back().map(|(k, _)| k)
+    }
+
+    pub fn clear(&mut self) {
+        self.map.clear();
+    }
+
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &T> + ExactSizeIterator {
+        self.map.iter().map(|(k, _)| k)
+    }
+}
+
+impl<T: Hash + Eq + Clone> Extend<T> for LinkedHashSet<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for value in iter {
+            self.insert(value);
+        }
+    }
+}
+
+impl<T: Hash + Eq + Clone> FromIterator<T> for LinkedHashSet<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut set = Self::new();
+        set.extend(iter);
+        set
+    }
+}
+
+impl<T> IntoIterator for LinkedHashSet<T> {
+    type Item = T;
+    type IntoIter = std::iter::Map<IntoIter<T, ()>, fn((T, ())) -> T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.map.into_iter().map(|(k, _)| k)
+    }
+}
+
+impl<T: Hash + Eq + Clone + fmt::Debug> fmt::Debug for LinkedHashSet<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LinkedHashMap, LinkedHashSet};
+    use crate::test_util::XorShift;
+
+    fn keys(map: &LinkedHashMap<u32, u32>) -> Vec<u32> {
+        map.keys().copied().collect()
+    }
+
+    #[test]
+    fn insertion_order() {
+        let mut map = LinkedHashMap::new();
+        assert!(map.is_empty());
+        for k in [3, 1, 2] {
+            assert_eq!(map.insert(k, k * 10), None);
+        }
+        assert_eq!(keys(&map), vec![3, 1, 2]);
+        assert_eq!(map.keys().rev().copied().collect::<Vec<_>>(), vec![2, 1, 3]);
+
+        // Replacing a value keeps the key where it was
+        assert_eq!(map.insert(3, 31), Some(30));
+        assert_eq!(keys(&map), vec![3, 1, 2]);
+        assert_eq!(map.get(&3), Some(&31));
+        *map.get_mut(&1).unwrap() += 1;
+        assert_eq!(map.values().copied().collect::<Vec<_>>(), vec![31, 11, 20]);
+
+        assert_eq!(map.remove(&1), Some(11));
+        assert_eq!(map.remove(&1), None);
+        assert!(!map.contains_key(&1));
+        map.insert(1, 12);
+        assert_eq!(keys(&map), vec![3, 2, 1]);
+        assert_eq!(map.len(), 3);
+        assert_eq!(format!("{:?}", map), "{3: 31, 2: 20, 1: 12}");
+    }
+
+    #[test]
+    fn moves_and_pops() {
+        let mut map: LinkedHashMap<_, _> = (1..=4).map(|k| (k, k * 10)).collect();
+
+        assert!(map.move_to_front(&3));
+        assert_eq!(keys(&map), vec![3, 1, 2, 4]);
+        assert!(map.move_to_back(&1));
+        assert_eq!(keys(&map), vec![3, 2, 4, 1]);
+        assert!(map.move_to_back(&1));
+        assert!(!map.move_to_front(&5));
+        assert_eq!(keys(&map), vec![3, 2, 4, 1]);
+
+        assert_eq!(map.front(), Some((&3, &30)));
+        assert_eq!(map.back(), Some((&1, &10)));
+        assert_eq!(map.pop_front(), Some((3, 30)));
+        assert_eq!(map.pop_back(), Some((1, 10)));
+        assert!(!map.contains_key(&3));
+        assert_eq!(map.get(&1), None);
+
+        assert_eq!(
+            map.iter().rev().collect::<Vec<_>>(),
+            vec![(&4, &40), (&2, &20)]
+        );
+        map.clear();
+        assert_eq!(map.pop_front(), None);
+        assert_eq!(map.back(), None);
+    }
+
+    #[test]
+    fn borrowed_keys() {
+        let mut map = LinkedHashMap::new();
+        map.insert(String::from("a"), 1);
+        map.insert(String::from("b"), 2);
+        assert!(map.move_to_back("a"));
+        assert_eq!(map.get("a"), Some(&1));
+        assert_eq!(map.remove_entry("b"), Some((String::from("b"), 2)));
+    }
+
+    #[test]
+    fn set() {
+        let mut set: LinkedHashSet<_> = [5, 3, 5, 1].into_iter().collect();
+        assert_eq!(set.iter().copied().collect::<Vec<_>>(), vec![5, 3, 1]);
+        assert!(!set.insert(3));
+        assert!(set.insert(4));
+        assert!(set.contains(&4));
+
+        assert!(set.move_to_front(&1));
+        assert!(set.move_to_back(&5));
+        assert_eq!(format!("{:?}", set), "{1, 3, 4, 5}");
+        assert_eq!(
+            set.iter().rev().copied().collect::<Vec<_>>(),
+            vec![5, 4, 3, 1]
+        );
+
+        assert!(set.remove(&3));
+        assert!(!set.remove(&3));
+        assert_eq!((set.front(), set.back()), (Some(&1), Some(&5)));
+        assert_eq!(set.pop_front(), Some(1));
+        assert_eq!(set.pop_back(), Some(5));
+        assert_eq!(set.len(), 1);
+        assert_eq!(set.into_iter().collect::<Vec<_>>(), vec![4]);
+    }
+
+    #[test]
+    fn matches_vec() {
+        let iterations = if cfg!(miri) { 200 } else { 20_000 };
+
+        for seed in 1..=5u64 {
+            let mut rng = XorShift(seed.wrapping_mul(0x9E37_79B9_7F4A_7C15));
+            let mut map = LinkedHashMap::new();
+            let mut model: Vec<(u32, u32)> = Vec::new();
+
+            for _ in 0..iterations {
+                let key = rng.next(16);
+                let position = model.iter().position(|&(k, _)| k == key);
+                match rng.next(100) {
+                    0..=19 => {
+                        let expected = position.map(|i| model[i].1);
+                        assert_eq!(map.get(&key).copied(), expected);
+                    }
+                    20..=54 => {
+                        let value = rng.next(1000);
+                        let old = match position {
+                            Some(i) => Some(std::mem::replace(&mut model[i].1, value)),
+                            None => {
+                                model.push((key, value));
+                                None
+                            }
+                        };
+                        assert_eq!(map.insert(key, value), old);
+                    }
+                    55..=69 => {
+                        let expected = position.map(|i| model.remove(i).1);
+                        assert_eq!(map.remove(&key), expected);
+                    }
+                    70..=77 => {
+                        if let Some(i) = position {
+                            let entry = model.remove(i);
+                            model.insert(0, entry);
+                        }
+                        assert_eq!(map.move_to_front(&key), position.is_some());
+                    }
+                    78..=85 => {
+                        if let Some(i) = position {
+                            let entry = model.remove(i);
+                            model.push(entry);
+                        }
+                        assert_eq!(map.move_to_back(&key), position.is_some());
+                    }
+                    86..=92 => {
+                        let expected = (!model.is_empty()).then(|| model.remove(0));
+                        assert_eq!(map.pop_front(), expected);
+                    }
+                    _ => assert_eq!(map.pop_back(), model.pop()),
+                }
+
+                let entries: Vec<_> = map.iter().map(|(&k, &v)| (k, v)).collect();
+                assert_eq!(entries, model);
+                assert_eq!(map.len(), model.len());
+            }
+
+            let backwards: Vec<_> = map.into_iter().rev().collect();
+            model.reverse();
+            assert_eq!(backwards, model);
+        }
+    }
+}
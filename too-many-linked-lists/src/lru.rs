@@ -0,0 +1,378 @@
+use std::borrow::Borrow;
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use crate::slab_list::{self, Handle};
+
+type EvictFn<K, V> = Box<dyn FnMut(K, V)>;
+
+// The recency list keeps the most recently used entry at the front. The map
+// points every key at its entry's handle, so finding, promoting and removing
+// an entry are all O(1).
+pub struct LruCache<K, V> {
+    map: HashMap<K, Handle>,
+    list: slab_list::List<(K, V)>,
+    capacity: usize,
+    on_evict: Option<EvictFn<K, V>>,
+}
+
+pub struct Iter<'a, K, V> {
+    inner: slab_list::Iter<'a, (K, V)>,
+}
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|(k, v)| (k, v))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<K, V> DoubleEndedIterator for Iter<'_, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back().map(|(k, v)| (k, v))
+    }
+}
+
+impl<K: Hash + Eq + Clone, V> LruCache<K, V> {
+    pub fn new(capacity: usize) -> Self {
+        LruCache {
+            map: HashMap::with_capacity(capacity),
+            list: slab_list::List::with_capacity(capacity),
+            capacity,
+            on_evict: None,
+        }
+    }
+
+    // `on_evict` is called with every entry pushed out for lack of room, by
+    // either `put` or `resize`. Entries removed explicitly don't trigger it.
+    pub fn with_on_evict<F>(capacity: usize, on_evict: F) -> Self
+    where
+        F: FnMut(K, V) + 'static,
+    {
+        LruCache {
+            on_evict: Some(Box::new(on_evict)),
+            ..LruCache::new(capacity)
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.list.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.list.is_empty()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn contains<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.map.contains_key(key)
+    }
+
+    pub fn get<Q>(&mut self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.get_mut(key).map(|v| &*v)
+    }
+
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let handle = *self.map.get(key)?;
+        self.list.move_to_front(handle)?;
+        self.list.get_mut(handle).map(|(_, v)| v)
+    }
+
+    // Like `get`, but leaves the entry's recency alone.
+    pub fn peek<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let handle = *self.map.get(key)?;
+        self.list.get(handle).map(|(_, v)| v)
+    }
+
+    pub fn peek_lru(&self) -> Option<(&K, &V)> {
+        self.list.back().map(|(k, v)| (k, v))
+    }
+
+    // Returns the previous value if `key` was already cached. Otherwise the
+    // least recently used entry is evicted if the cache is full.
+    pub fn put(&mut self, key: K, value: V) -> Option<V> {
+        if let Some(&handle) = self.map.get(&key) {
+            self.list.move_to_front(handle)?;
+            let (_, old) = self.list.get_mut(handle)?;
+            return Some(std::mem::replace(old, value));
+        }
+
+        let handle = self.list.push_front((key.clone(), value));
+        self.map.insert(key, handle);
+        self.evict_to(self.capacity);
+        None
+    }
+
+    pub fn pop<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let handle = self.map.remove(key)?;
+        self.list.remove(handle).map(|(_, v)| v)
+    }
+
+    pub fn pop_lru(&mut self) -> Option<(K, V)> {
+        let (key, value) = self.list.pop_back()?;
+        self.map.remove(&key);
+        Some((key, value))
+    }
+
+    // Shrinking evicts least recently used entries until the cache fits.
+    pub fn resize(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        self.evict_to(capacity);
+    }
+
+    pub fn clear(&mut self) {
+        self.map.clear();
+        self.list.clear();
+    }
+
+    // Most recently used first.
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        Iter {
+            inner: self.list.iter(),
+        }
+    }
+
+    fn evict_to(&mut self, capacity: usize) {
+        while self.len() > capacity {
+            if let Some((key, value)) = self.pop_lru() {
+                if let Some(on_evict) = self.on_evict.as_mut() {
+                    on_evict(key, value);
+                }
+            }
+        }
+    }
+}
+
+impl<'a, K: Hash + Eq + Clone, V> IntoIterator for &'a LruCache<K, V> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LruCache;
+    use crate::test_util::XorShift;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    fn keys(cache: &LruCache<u32, u32>) -> Vec<u32> {
+        cache.iter().map(|(&k, _)| k).collect()
+    }
+
+    #[test]
+    fn get_put() {
+        let mut cache = LruCache::new(2);
+        assert!(cache.is_empty());
+        assert_eq!(cache.put(1, 10), None);
+        assert_eq!(cache.put(2, 20), None);
+        assert_eq!(keys(&cache), vec![2, 1]);
+
+        assert_eq!(cache.get(&1), Some(&10));
+        assert_eq!(keys(&cache), vec![1, 2]);
+
+        // 2 is the least recently used now
+        assert_eq!(cache.put(3, 30), None);
+        assert_eq!(keys(&cache), vec![3, 1]);
+        assert_eq!(cache.get(&2), None);
+        assert!(!cache.contains(&2));
+
+        assert_eq!(cache.put(1, 11), Some(10));
+        assert_eq!(keys(&cache), vec![1, 3]);
+        assert_eq!(cache.len(), 2);
+
+        *cache.get_mut(&3).unwrap() += 3;
+        assert_eq!(cache.peek(&3), Some(&33));
+    }
+
+    #[test]
+    fn peek_does_not_promote() {
+        let mut cache = LruCache::new(2);
+        cache.put(1, 10);
+        cache.put(2, 20);
+
+        assert_eq!(cache.peek(&1), Some(&10));
+        assert_eq!(cache.peek_lru(), Some((&1, &10)));
+        cache.put(3, 30);
+        assert_eq!(cache.peek(&1), None);
+        assert_eq!(keys(&cache), vec![3, 2]);
+    }
+
+    #[test]
+    fn pop_and_pop_lru() {
+        let mut cache = LruCache::new(3);
+        cache.put(1, 10);
+        cache.put(2, 20);
+        cache.put(3, 30);
+
+        assert_eq!(cache.pop(&2), Some(20));
+        assert_eq!(cache.pop(&2), None);
+        assert_eq!(cache.pop_lru(), Some((1, 10)));
+        assert_eq!(cache.pop_lru(), Some((3, 30)));
+        assert_eq!(cache.pop_lru(), None);
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn resize_and_eviction_callback() {
+        let evicted = Rc::new(RefCell::new(Vec::new()));
+        let mut cache = LruCache::with_on_evict(3, {
+            let evicted = evicted.clone();
+            move |k, v| evicted.borrow_mut().push((k, v))
+        });
+
+        for i in 1..=4 {
+            cache.put(i, i * 10);
+        }
+        assert_eq!(*evicted.borrow(), vec![(1, 10)]);
+
+        cache.get(&2);
+        cache.resize(1);
+        assert_eq!(cache.capacity(), 1);
+        assert_eq!(*evicted.borrow(), vec![(1, 10), (3, 30), (4, 40)]);
+        assert_eq!(keys(&cache), vec![2]);
+
+        // Explicit removals don't go through the callback
+        cache.pop(&2);
+        cache.put(5, 50);
+        cache.pop_lru();
+        assert_eq!(evicted.borrow().len(), 3);
+
+        cache.resize(0);
+        cache.put(6, 60);
+        assert!(cache.is_empty());
+        assert_eq!(evicted.borrow().last(), Some(&(6, 60)));
+    }
+
+    #[test]
+    fn borrowed_keys() {
+        let mut cache = LruCache::new(2);
+        cache.put(String::from("a"), 1);
+        assert_eq!(cache.get("a"), Some(&1));
+        assert_eq!(cache.peek("a"), Some(&1));
+        assert_eq!(cache.pop("a"), Some(1));
+    }
+
+    // Straightforward O(n) LRU to check against; front is most recent.
+    struct Reference {
+        entries: Vec<(u32, u32)>,
+        capacity: usize,
+    }
+
+    impl Reference {
+        fn position(&self, key: u32) -> Option<usize> {
+            self.entries.iter().position(|&(k, _)| k == key)
+        }
+
+        fn get(&mut self, key: u32) -> Option<u32> {
+            let entry = self.entries.remove(self.position(key)?);
+            self.entries.insert(0, entry);
+            Some(entry.1)
+        }
+
+        fn peek(&self, key: u32) -> Option<u32> {
+            self.position(key).map(|i| self.entries[i].1)
+        }
+
+        fn put(&mut self, key: u32, value: u32) -> (Option<u32>, Vec<(u32, u32)>) {
+            let old = self.position(key).map(|i| self.entries.remove(i).1);
+            self.entries.insert(0, (key, value));
+            (old, self.shrink())
+        }
+
+        fn resize(&mut self, capacity: usize) -> Vec<(u32, u32)> {
+            self.capacity = capacity;
+            self.shrink()
+        }
+
+        fn shrink(&mut self) -> Vec<(u32, u32)> {
+            let mut evicted = Vec::new();
+            while self.entries.len() > self.capacity {
+                evicted.push(self.entries.pop().unwrap());
+            }
+            evicted
+        }
+    }
+
+    #[test]
+    fn matches_reference() {
+        let iterations = if cfg!(miri) { 200 } else { 20_000 };
+
+        for seed in 1..=5u64 {
+            let mut rng = XorShift(seed.wrapping_mul(0x9E37_79B9_7F4A_7C15));
+            let evicted = Rc::new(RefCell::new(Vec::new()));
+            let mut cache = LruCache::with_on_evict(8, {
+                let evicted = evicted.clone();
+                move |k, v| evicted.borrow_mut().push((k, v))
+            });
+            let mut reference = Reference {
+                entries: Vec::new(),
+                capacity: 8,
+            };
+
+            for _ in 0..iterations {
+                let key = rng.next(16);
+                match rng.next(100) {
+                    0..=34 => assert_eq!(cache.get(&key).copied(), reference.get(key)),
+                    35..=44 => assert_eq!(cache.peek(&key).copied(), reference.peek(key)),
+                    45..=84 => {
+                        let value = rng.next(1000);
+                        let (old, expected) = reference.put(key, value);
+                        assert_eq!(cache.put(key, value), old);
+                        assert_eq!(evicted.replace(Vec::new()), expected);
+                    }
+                    85..=91 => {
+                        let expected = reference
+                            .position(key)
+                            .map(|i| reference.entries.remove(i).1);
+                        assert_eq!(cache.pop(&key), expected);
+                    }
+                    92..=96 => {
+                        let expected = reference.entries.pop();
+                        assert_eq!(cache.pop_lru(), expected);
+                    }
+                    _ => {
+                        let capacity = rng.next(12) as usize;
+                        let expected = reference.resize(capacity);
+                        cache.resize(capacity);
+                        assert_eq!(evicted.replace(Vec::new()), expected);
+                    }
+                }
+
+                let entries: Vec<_> = cache.iter().map(|(&k, &v)| (k, v)).collect();
+                assert_eq!(entries, reference.entries);
+            }
+        }
+    }
+}
@@ -0,0 +1,302 @@
+use alloc::alloc::{AllocError, Allocator, Global, Layout};
+use alloc::vec::Vec;
+use core::cell::{Cell, RefCell};
+use core::ptr::{self, NonNull};
+
+const DEFAULT_CHUNK_LEN: usize = 64;
+
+// A single-threaded pool for fixed-size blocks, meant for list nodes: hand
+// `&pool` to a list (`second::List::new_in(&pool)`) and every node comes out
+// of it.
+//
+// The first allocation fixes the block layout. Blocks are bump-allocated out
+// of chunks of `chunk_len` blocks, and freed blocks go onto a free list to be
+// handed out again before any fresh ones. Requests that don't fit a block, or
+// that need no memory at all, are passed through to the global allocator.
+// Chunks are only given back when the pool itself is dropped.
+pub struct NodePool {
+    block: Cell<Option<Layout>>,
+    chunk_len: usize,
+    chunks: RefCell<Vec<NonNull<u8>>>,
+    // The unused part of the newest chunk
+    bump: Cell<*mut u8>,
+    end: Cell<*mut u8>,
+    free: Cell<Option<NonNull<FreeBlock>>>,
+    free_len: Cell<usize>,
+}
+
+struct FreeBlock {
+    next: Option<NonNull<FreeBlock>>,
+}
+
+impl NodePool {
+    pub fn new() -> Self {
+        NodePool::with_chunk_len(DEFAULT_CHUNK_LEN)
+    }
+
+    pub fn with_chunk_len(chunk_len: usize) -> Self {
+        assert!(chunk_len > 0, "chunks must hold at least one block");
+        NodePool {
+            block: Cell::new(None),
+            chunk_len,
+            chunks: RefCell::new(Vec::new()),
+            bump: Cell::new(ptr::null_mut()),
+            end: Cell::new(ptr::null_mut()),
+            free: Cell::new(None),
+            free_len: Cell::new(0),
+        }
+    }
+
+    // How many chunks have been taken from the global allocator so far.
+    pub fn chunks(&self) -> usize {
+        self.chunks.borrow().len()
+    }
+
+    // How many freed blocks are waiting to be reused.
+    pub fn free_blocks(&self) -> usize {
+        self.free_len.get()
+    }
+
+    fn fits(&self, layout: Layout) -> bool {
+        layout.size() > 0
+            && self.block.get().is_some_and(|block| {
+                layout.size() <= block.size() && layout.align() <= block.align()
+            })
+    }
+
+    fn chunk_layout(&self) -> Layout {
+        let block = self.block.get().unwrap();
+        Layout::from_size_align(block.size() * self.chunk_len, block.align()).unwrap()
+    }
+
+    fn alloc_block(&self) -> Result<NonNull<u8>, AllocError> {
+        if let Some(head) = self.free.get() {
+            unsafe {
+                self.free.set((*head.as_ptr()).next);
+            }
+            self.free_len.set(self.free_len.get() - 1);
+            return Ok(head.cast());
+        }
+
+        if self.bump.get() == self.end.get() {
+            let chunk = Global.allocate(self.chunk_layout())?.cast::<u8>();
+            self.chunks.borrow_mut().push(chunk);
+            self.bump.set(chunk.as_ptr());
+            self.end
+                .set(unsafe { chunk.as_ptr().add(self.chunk_layout().size()) });
+        }
+
+        let block = self.bump.get();
+        self.bump
+            .set(unsafe { block.add(self.block.get().unwrap().size()) });
+        Ok(unsafe { NonNull::new_unchecked(block) })
+    }
+}
+
+impl Default for NodePool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+unsafe impl Allocator for NodePool {
+    fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+        if self.block.get().is_none() && layout.size() > 0 {
+            // Every block has to be able to hold a free list link
+            let block = Layout::new::<FreeBlock>();
+            let size = layout.size().max(block.size());
+            let align = layout.align().max(block.align());
+            let block = Layout::from_size_align(size, align).map_err(|_| AllocError)?;
+            self.block.set(Some(block.pad_to_align()));
+        }
+
+        if self.fits(layout) {
+            let block = self.alloc_block()?;
+            Ok(NonNull::slice_from_raw_parts(block, layout.size()))
+        } else {
+            Global.allocate(layout)
+        }
+    }
+
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        if self.fits(layout) {
+            let block = ptr.cast::<FreeBlock>();
+            unsafe {
+                block.as_ptr().write(FreeBlock {
+                    next: self.free.get(),
+                });
+            }
+            self.free.set(Some(block));
+            self.free_len.set(self.free_len.get() + 1);
+        } else {
+            unsafe { Global.deallocate(ptr, layout) }
+        }
+    }
+}
+
+/// An allocator that can tell whether another one is the same allocator.
+/// Lists check this before taking over another list's nodes, since a node is
+/// freed by whichever list it ends up in.
+///
+/// # Safety
+///
+/// `same_as` may only return true if memory from either allocator can be
+/// freed through the other.
+pub unsafe trait SameAllocator: Allocator {
+    fn same_as(&self, other: &Self) -> bool;
+}
+
+unsafe impl SameAllocator for Global {
+    fn same_as(&self, _: &Self) -> bool {
+        true
+    }
+}
+
+unsafe impl SameAllocator for NodePool {
+    fn same_as(&self, other: &Self) -> bool {
+        ptr::eq(self, other)
+    }
+}
+
+unsafe impl<A: SameAllocator + ?Sized> SameAllocator for &A {
+    fn same_as(&self, other: &Self) -> bool {
+        (**self).same_as(other)
+    }
+}
+
+impl Drop for NodePool {
+    fn drop(&mut self) {
+        if self.block.get().is_none() {
+            return;
+        }
+        let layout = self.chunk_layout();
+        for chunk in self.chunks.get_mut().drain(..) {
+            unsafe { Global.deallocate(chunk, layout) }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::NodePool;
+    use crate::{second, sixth};
+    use std::alloc::{Allocator, Layout};
+
+    #[test]
+    fn recycles_blocks() {
+        let pool = NodePool::with_chunk_len(4);
+        let layout = Layout::new::<[u64; 2]>();
+
+        let blocks: Vec<_> = (0..6).map(|_| pool.allocate(layout).unwrap()).collect();
+        assert_eq!(pool.chunks(), 2);
+        assert_eq!(pool.free_blocks(), 0);
+
+        for &block in &blocks[..3] {
+            unsafe { pool.deallocate(block.cast(), layout) };
+        }
+        assert_eq!(pool.free_blocks(), 3);
+
+        // Freed blocks come back, most recently freed first
+        let again = pool.allocate(layout).unwrap();
+        assert_eq!(again.cast::<u8>(), blocks[2].cast());
+        assert_eq!(pool.free_blocks(), 2);
+        assert_eq!(pool.chunks(), 2);
+
+        for &block in blocks[3..].iter().chain([&again]) {
+            unsafe { pool.deallocate(block.cast(), layout) };
+        }
+        assert_eq!(pool.free_blocks(), 6);
+    }
+
+    #[test]
+    fn passes_other_layouts_through() {
+        let pool = NodePool::with_chunk_len(2);
+        let small = Layout::new::<u8>();
+        let big = Layout::new::<[u64; 8]>();
+
+        // `small` sets the block size, `big` doesn't fit in it
+        let a = pool.allocate(small).unwrap();
+        let b = pool.allocate(big).unwrap();
+        let empty = pool.allocate(Layout::new::<()>()).unwrap();
+        assert_eq!(pool.chunks(), 1);
+        assert_eq!(b.len(), big.size());
+
+        unsafe {
+            pool.deallocate(b.cast(), big);
+            pool.deallocate(empty.cast(), Layout::new::<()>());
+            pool.deallocate(a.cast(), small);
+        }
+        assert_eq!(pool.free_blocks(), 1);
+    }
+
+    #[test]
+    fn backs_lists() {
+        let pool = NodePool::with_chunk_len(16);
+        let mut stack = second::List::new_in(&pool);
+        stack.extend(0..100);
+        let chunks = pool.chunks();
+        assert_eq!(chunks, 7);
+
+        let mut odds = stack.split_off(50);
+        odds.retain(|n| n % 2 == 1);
+        assert_eq!(pool.free_blocks(), 25);
+        stack.append(&mut odds);
+        stack.sort_by_key(|&n| std::cmp::Reverse(n));
+        assert_eq!(stack.peek(), Some(&99));
+        assert_eq!(stack.len(), 75);
+
+        stack.extend(0..25);
+        assert_eq!(pool.free_blocks(), 0);
+        assert_eq!(pool.chunks(), chunks);
+        drop(stack);
+        assert_eq!(pool.free_blocks(), 100);
+
+        // Both kinds of node are the same size, so they share blocks
+        let mut deque = sixth::LinkedList::new_in(&pool);
+        for i in 0..100 {
+            deque.push_back(i);
+        }
+        assert_eq!(pool.chunks(), chunks);
+        deque.clear();
+        assert_eq!(pool.free_blocks(), 100);
+    }
+
+    #[test]
+    fn owned_by_a_list() {
+        // The pool isn't `Clone`, so the list has the only handle to it
+        let mut stack = second::List::new_in(NodePool::with_chunk_len(16));
+        stack.extend(0..50);
+        stack.push(50);
+        stack.retain(|n| n % 5 != 0);
+        assert_eq!(stack.allocator().free_blocks(), 11);
+
+        stack.extend([1, 1, 1]);
+        stack.sort();
+        stack.dedup();
+        assert_eq!(stack.peek(), Some(&1));
+        assert_eq!(stack.len(), 40);
+        assert_eq!(stack.allocator().free_blocks(), 11);
+    }
+
+    #[test]
+    #[should_panic(expected = "different allocators")]
+    fn append_rejects_another_pool() {
+        let (pool_a, pool_b) = (NodePool::new(), NodePool::new());
+        let mut a = second::List::new_in(&pool_a);
+        let mut b = second::List::new_in(&pool_b);
+        a.push(1);
+        b.push(2);
+        a.append(&mut b);
+    }
+
+    #[test]
+    #[should_panic(expected = "different allocators")]
+    fn splice_rejects_another_pool() {
+        let (pool_a, pool_b) = (NodePool::new(), NodePool::new());
+        let mut a = sixth::LinkedList::new_in(&pool_a);
+        let mut b = sixth::LinkedList::new_in(&pool_b);
+        a.push_back(1);
+        b.push_back(2);
+        a.cursor_mut().splice_after(b);
+    }
+}
@@ -1,9 +1,28 @@
-type Link<T> = Option<Box<Node<T>>>;
-
-pub struct IntoIter<T>(List<T>);
-
-pub struct List<T> {
+use alloc::alloc::{Allocator, Global};
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+use core::fmt;
+use core::hash::{Hash, Hasher};
+use core::marker::PhantomData;
+use core::ptr::NonNull;
+
+use crate::pool::SameAllocator;
+use crate::traits::{Collection, Stack};
+use crate::viz::{self, Graph, ToDot};
+
+type Link<T> = Option<NonNull<Node<T>>>;
+
+pub struct IntoIter<T, A: Allocator = Global>(List<T, A>);
+
+// Like sixth's, the allocator lives in the list rather than in every node,
+// and a node is freed by whichever list it ends up in. So `append` only
+// takes nodes from a list with the same allocator.
+pub struct List<T, A: Allocator = Global> {
     head: Link<T>,
+    alloc: A,
+    _boo: PhantomData<T>,
 }
 
 pub struct Iter<'a, T> {
@@ -15,7 +34,7 @@ impl<'a, T> Iterator for Iter<'a, T> {
 
     fn next(&mut self) -> Option<Self::Item> {
         self.next.map(|node| {
-            self.next = node.next.as_deref();
+            self.next = node.next.map(|next| unsafe { &*next.as_ptr() });
             &node.elem
         })
     }
@@ -30,13 +49,13 @@ impl<'a, T> Iterator for IterMut<'a, T> {
 
     fn next(&mut self) -> Option<Self::Item> {
         self.next.take().map(|node| {
-            self.next = node.next.as_deref_mut();
+            self.next = node.next.map(|next| unsafe { &mut *next.as_ptr() });
             &mut node.elem
         })
     }
 }
 
-impl<T> Iterator for IntoIter<T> {
+impl<T, A: Allocator> Iterator for IntoIter<T, A> {
     type Item = T;
 
     fn next(&mut self) -> Option<Self::Item> {
@@ -46,67 +65,412 @@ impl<T> Iterator for IntoIter<T> {
 
 impl<T> List<T> {
     pub fn new() -> Self {
-        List { head: None }
+        List::new_in(Global)
     }
+}
 
-    pub fn into_iter(self) -> IntoIter<T> {
-        IntoIter(self)
+impl<T, A: Allocator> List<T, A> {
+    pub fn new_in(alloc: A) -> Self {
+        List {
+            head: None,
+            alloc,
+            _boo: PhantomData,
+        }
+    }
+
+    pub fn allocator(&self) -> &A {
+        &self.alloc
     }
 
     pub fn iter(&self) -> Iter<'_, T> {
         Iter {
-            next: self.head.as_deref(),
+            next: self.head.map(|node| unsafe { &*node.as_ptr() }),
         }
     }
 
     pub fn iter_mut(&mut self) -> IterMut<'_, T> {
         IterMut {
-            next: self.head.as_deref_mut(),
+            next: self.head.map(|node| unsafe { &mut *node.as_ptr() }),
         }
     }
 
     pub fn push(&mut self, elem: T) {
-        let new_node = Node {
-            elem,
-            next: self.head.take(),
-        };
-
-        self.head = Some(Box::new(new_node));
+        let new_node = Box::new_in(
+            Node {
+                elem,
+                next: self.head,
+            },
+            &self.alloc,
+        );
+
+        self.head = Some(NonNull::from(Box::leak(new_node)));
     }
 
     pub fn pop(&mut self) -> Option<T> {
-        self.head.take().map(|node| {
+        self.head.map(|node| unsafe {
+            let node = Box::from_raw_in(node.as_ptr(), &self.alloc);
             self.head = node.next;
             node.elem
         })
     }
 
     pub fn peek(&self) -> Option<&T> {
-        self.head.as_ref().map(|node| &node.elem)
+        self.head.map(|node| unsafe { &(*node.as_ptr()).elem })
     }
 
     pub fn peek_mut(&mut self) -> Option<&mut T> {
-        self.head.as_mut().map(|node| &mut node.elem)
+        self.head.map(|node| unsafe { &mut (*node.as_ptr()).elem })
+    }
+
+    pub fn len(&self) -> usize {
+        self.iter().count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.head.is_none()
+    }
+
+    pub fn reverse(&mut self) {
+        let mut rest = self.head.take();
+        while let Some(node) = rest {
+            unsafe {
+                rest = (*node.as_ptr()).next;
+                (*node.as_ptr()).next = self.head;
+            }
+            self.head = Some(node);
+        }
+    }
+
+    pub fn retain<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&T) -> bool,
+    {
+        let mut link = &mut self.head;
+        while let Some(node) = *link {
+            unsafe {
+                if f(&(*node.as_ptr()).elem) {
+                    link = &mut (*node.as_ptr()).next;
+                } else {
+                    let node = Box::from_raw_in(node.as_ptr(), &self.alloc);
+                    *link = node.next;
+                }
+            }
+        }
+    }
+
+    pub fn dedup(&mut self)
+    where
+        T: PartialEq,
+    {
+        self.dedup_by(|a, b| a == b)
+    }
+
+    // Drops every element for which `same_bucket(elem, prev)` holds, where
+    // `prev` is the closest element before it that was kept.
+    pub fn dedup_by<F>(&mut self, mut same_bucket: F)
+    where
+        F: FnMut(&T, &T) -> bool,
+    {
+        let mut cur = self.head;
+        while let Some(node) = cur {
+            unsafe {
+                while let Some(next) = (*node.as_ptr()).next {
+                    if !same_bucket(&(*next.as_ptr()).elem, &(*node.as_ptr()).elem) {
+                        break;
+                    }
+                    let next = Box::from_raw_in(next.as_ptr(), &self.alloc);
+                    (*node.as_ptr()).next = next.next;
+                }
+                cur = (*node.as_ptr()).next;
+            }
+        }
+    }
+
+    pub fn sort(&mut self)
+    where
+        T: Ord,
+    {
+        self.sort_by(T::cmp)
+    }
+
+    pub fn sort_by_key<K, F>(&mut self, mut f: F)
+    where
+        K: Ord,
+        F: FnMut(&T) -> K,
+    {
+        self.sort_by(|a, b| f(a).cmp(&f(b)))
+    }
+
+    // Bottom-up merge sort. `bins[i]` is either empty or a sorted run of 2^i
+    // nodes, and every run in a higher bin holds earlier nodes than the runs
+    // below it, so always merging the higher bin in as the left side keeps
+    // the sort stable. Nothing recurses, and the runs are kept as `List`s
+    // borrowing our allocator, so a panicking comparison still frees them
+    // iteratively.
+    pub fn sort_by<F>(&mut self, mut compare: F)
+    where
+        F: FnMut(&T, &T) -> Ordering,
+    {
+        let alloc = &self.alloc;
+        let mut bins: Vec<Option<List<T, &A>>> = Vec::new();
+        let mut rest = List::new_in(alloc);
+        rest.head = self.head.take();
+
+        while let Some(node) = rest.head {
+            rest.head = unsafe { (*node.as_ptr()).next.take() };
+            let mut carry = List::new_in(alloc);
+            carry.head = Some(node);
+
+            let mut i = 0;
+            while let Some(bin) = bins.get_mut(i).and_then(Option::take) {
+                carry = merge(bin, carry, &mut compare);
+                i += 1;
+            }
+            if i == bins.len() {
+                bins.push(Some(carry));
+            } else {
+                bins[i] = Some(carry);
+            }
+        }
+
+        let mut sorted = List::new_in(alloc);
+        for bin in bins.into_iter().flatten() {
+            sorted = merge(bin, sorted, &mut compare);
+        }
+        self.head = sorted.head.take();
     }
 }
 
-impl<T> Drop for List<T> {
-    fn drop(&mut self) {
-        let mut cur_link = self.head.take();
-        while let Some(mut node) = cur_link {
-            cur_link = node.next.take();
+impl<T, A: Allocator + Clone> List<T, A> {
+    // Everything from index `at` onwards moves into the returned list.
+    pub fn split_off(&mut self, at: usize) -> List<T, A> {
+        let mut link = &mut self.head;
+        for _ in 0..at {
+            link = match *link {
+                Some(node) => unsafe { &mut (*node.as_ptr()).next },
+                None => panic!("split_off index out of bounds"),
+            };
+        }
+        let head = link.take();
+
+        let mut tail = List::new_in(self.alloc.clone());
+        tail.head = head;
+        tail
+    }
+}
+
+impl<T, A: SameAllocator> List<T, A> {
+    // Moves every node of `other` onto the end of `self`, leaving `other` empty.
+    // Panics if the lists use different allocators.
+    pub fn append(&mut self, other: &mut List<T, A>) {
+        assert!(
+            self.alloc.same_as(&other.alloc),
+            "can't move nodes between lists with different allocators"
+        );
+        if other.is_empty() {
+            return;
+        }
+        *last_link(&mut self.head) = other.head.take();
+    }
+}
+
+fn last_link<T>(mut link: &mut Link<T>) -> &mut Link<T> {
+    while let Some(node) = *link {
+        link = unsafe { &mut (*node.as_ptr()).next };
+    }
+    link
+}
+
+// Stable: on ties the node from `left` goes first.
+fn merge<T, A, F>(mut left: List<T, A>, mut right: List<T, A>, compare: &mut F) -> List<T, A>
+where
+    A: Allocator + Clone,
+    F: FnMut(&T, &T) -> Ordering,
+{
+    let mut merged = List::new_in(left.alloc.clone());
+    let mut tail = &mut merged.head;
+
+    while let (Some(l), Some(r)) = (left.head, right.head) {
+        let less = unsafe { compare(&(*r.as_ptr()).elem, &(*l.as_ptr()).elem) };
+        let (src, node) = if less == Ordering::Less {
+            (&mut right, r)
+        } else {
+            (&mut left, l)
+        };
+        unsafe {
+            src.head = (*node.as_ptr()).next.take();
+            *tail = Some(node);
+            tail = &mut (*node.as_ptr()).next;
+        }
+    }
+
+    *tail = left.head.take().or_else(|| right.head.take());
+    merged
+}
+
+impl<T, A: Allocator + Default> Default for List<T, A> {
+    fn default() -> Self {
+        Self::new_in(A::default())
+    }
+}
+
+impl<T: Clone, A: Allocator + Clone> Clone for List<T, A> {
+    fn clone(&self) -> Self {
+        let mut list = List::new_in(self.alloc.clone());
+        list.extend(self.iter().cloned());
+        list
+    }
+}
+
+impl<T: fmt::Debug, A: Allocator> fmt::Debug for List<T, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T: PartialEq, A: Allocator> PartialEq for List<T, A> {
+    fn eq(&self, other: &Self) -> bool {
+        self.iter().eq(other.iter())
+    }
+}
+
+impl<T: Eq, A: Allocator> Eq for List<T, A> {}
+
+impl<T: PartialOrd, A: Allocator> PartialOrd for List<T, A> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.iter().partial_cmp(other.iter())
+    }
+}
+
+impl<T: Ord, A: Allocator> Ord for List<T, A> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.iter().cmp(other.iter())
+    }
+}
+
+impl<T: Hash, A: Allocator> Hash for List<T, A> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.len().hash(state);
+        for elem in self.iter() {
+            elem.hash(state);
+        }
+    }
+}
+
+// Collecting keeps the iterator's order, so the first element ends up on top.
+impl<T> FromIterator<T> for List<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut list = List::new();
+        list.extend(iter);
+        list
+    }
+}
+
+// Appends after the bottom of the stack, in order.
+impl<T, A: Allocator> Extend<T> for List<T, A> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        let mut link = last_link(&mut self.head);
+        for elem in iter {
+            let node = Box::new_in(Node { elem, next: None }, &self.alloc);
+            let node = NonNull::from(Box::leak(node));
+            *link = Some(node);
+            link = unsafe { &mut (*node.as_ptr()).next };
         }
     }
 }
 
+impl<T, A: Allocator> IntoIterator for List<T, A> {
+    type Item = T;
+    type IntoIter = IntoIter<T, A>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter(self)
+    }
+}
+
+impl<'a, T, A: Allocator> IntoIterator for &'a List<T, A> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, T, A: Allocator> IntoIterator for &'a mut List<T, A> {
+    type Item = &'a mut T;
+    type IntoIter = IterMut<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+impl<T, A: Allocator> Drop for List<T, A> {
+    fn drop(&mut self) {
+        while self.pop().is_some() {}
+    }
+}
+
+unsafe impl<T: Send, A: Allocator + Send> Send for List<T, A> {}
+unsafe impl<T: Sync, A: Allocator + Sync> Sync for List<T, A> {}
+
 struct Node<T> {
     elem: T,
     next: Link<T>,
 }
 
+impl<T, A: Allocator> Collection<T> for List<T, A> {
+    type Ref<'a>
+        = &'a T
+    where
+        Self: 'a;
+
+    fn len(&self) -> usize {
+        List::len(self)
+    }
+
+    fn clear(&mut self) {
+        while self.pop().is_some() {}
+    }
+}
+
+impl<T, A: Allocator> Stack<T> for List<T, A> {
+    fn push(&mut self, elem: T) {
+        List::push(self, elem)
+    }
+
+    fn pop(&mut self) -> Option<T> {
+        List::pop(self)
+    }
+
+    fn peek(&self) -> Option<&T> {
+        List::peek(self)
+    }
+}
+
+impl<T: fmt::Debug, A: Allocator> ToDot for List<T, A> {
+    fn write_dot(&self, name: &str, graph: &mut Graph) {
+        let mut from = graph.root(name);
+        let mut attrs = "label=\"head\"";
+        let mut cur = self.head;
+        while let Some(node) = cur {
+            let ptr: *const Node<T> = node.as_ptr();
+            let node = unsafe { &*ptr };
+            graph.node(ptr, &format!("{:?}", node.elem));
+            graph.edge(&from, &viz::id(ptr), attrs);
+            from = viz::id(ptr);
+            attrs = "";
+            cur = node.next;
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::List;
+    use crate::test_util::hash_of;
+    use crate::test_util::XorShift;
 
     #[test]
     fn basics() {
@@ -127,7 +491,9 @@ mod tests {
         assert!(list.pop().is_none());
     }
 
+    // Kept as originally written, mutating through `map`
     #[test]
+    #[allow(clippy::option_map_unit_fn)]
     fn peek() {
         let mut list = List::new();
         assert!(list.peek().is_none());
@@ -178,6 +544,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::option_map_unit_fn)]
     fn iter_mut() {
         let mut list = List::new();
         list.push(1);
@@ -196,4 +563,198 @@ mod tests {
         assert_eq!(iter.next(), Some(&3));
         assert_eq!(iter.next(), Some(&102));
     }
+
+    fn list_from(elems: &[i32]) -> List<i32> {
+        elems.iter().copied().collect()
+    }
+
+    fn to_vec<T: Clone>(list: &List<T>) -> Vec<T> {
+        list.iter().cloned().collect()
+    }
+
+    fn addrs<T>(list: &List<T>) -> Vec<*const T> {
+        list.iter().map(|elem| elem as *const T).collect()
+    }
+
+    #[test]
+    fn len() {
+        let mut list = List::new();
+        assert_eq!(list.len(), 0);
+        assert!(list.is_empty());
+        list.push(1);
+        list.push(2);
+        assert_eq!(list.len(), 2);
+        assert!(!list.is_empty());
+    }
+
+    #[test]
+    fn reverse() {
+        let mut list = list_from(&[1, 2, 3, 4]);
+        let before = addrs(&list);
+        list.reverse();
+        assert_eq!(to_vec(&list), vec![4, 3, 2, 1]);
+
+        // Same nodes, just relinked
+        let mut after = addrs(&list);
+        after.reverse();
+        assert_eq!(after, before);
+
+        let mut empty = List::<i32>::new();
+        empty.reverse();
+        assert!(empty.is_empty());
+    }
+
+    #[test]
+    fn split_off_and_append() {
+        let mut list = list_from(&[1, 2, 3, 4, 5]);
+        let mut tail = list.split_off(2);
+        assert_eq!(to_vec(&list), vec![1, 2]);
+        assert_eq!(to_vec(&tail), vec![3, 4, 5]);
+
+        assert!(list.split_off(2).is_empty());
+        let mut all = list.split_off(0);
+        assert!(list.is_empty());
+        assert_eq!(to_vec(&all), vec![1, 2]);
+
+        all.append(&mut tail);
+        assert!(tail.is_empty());
+        assert_eq!(to_vec(&all), vec![1, 2, 3, 4, 5]);
+
+        list.append(&mut all);
+        assert_eq!(to_vec(&list), vec![1, 2, 3, 4, 5]);
+        list.append(&mut all);
+        assert_eq!(list.len(), 5);
+    }
+
+    #[test]
+    #[should_panic]
+    fn split_off_out_of_bounds() {
+        list_from(&[1, 2]).split_off(3);
+    }
+
+    #[test]
+    fn retain() {
+        let mut list = list_from(&[1, 2, 3, 4, 5, 6]);
+        list.retain(|&x| x % 2 == 0);
+        assert_eq!(to_vec(&list), vec![2, 4, 6]);
+        list.retain(|&x| x > 2);
+        assert_eq!(to_vec(&list), vec![4, 6]);
+        list.retain(|_| false);
+        assert!(list.is_empty());
+    }
+
+    #[test]
+    fn dedup() {
+        let mut list = list_from(&[1, 1, 2, 3, 3, 3, 1, 4, 4]);
+        list.dedup();
+        assert_eq!(to_vec(&list), vec![1, 2, 3, 1, 4]);
+
+        let mut list = list_from(&[1, 2, 4, 5, 7, 10, 11]);
+        list.dedup_by(|a, b| a - b == 1);
+        assert_eq!(to_vec(&list), vec![1, 4, 7, 10]);
+    }
+
+    #[test]
+    fn sort() {
+        let mut list = list_from(&[5, 3, 8, 1, 9, 2, 7]);
+        let mut before = addrs(&list);
+        list.sort();
+        assert_eq!(to_vec(&list), vec![1, 2, 3, 5, 7, 8, 9]);
+
+        let mut after = addrs(&list);
+        before.sort();
+        after.sort();
+        assert_eq!(after, before);
+
+        list.sort_by(|a, b| b.cmp(a));
+        assert_eq!(to_vec(&list), vec![9, 8, 7, 5, 3, 2, 1]);
+    }
+
+    #[test]
+    fn sort_is_stable() {
+        let mut rng = XorShift(0x2545_F491_4F6C_DD1D);
+        let mut list = List::new();
+        for i in 0..1000 {
+            list.push((rng.next(10), i));
+        }
+        let mut expected = to_vec(&list);
+
+        list.sort_by_key(|&(key, _)| key);
+        expected.sort_by_key(|&(key, _)| key);
+        assert_eq!(to_vec(&list), expected);
+    }
+
+    #[test]
+    fn sort_matches_vec() {
+        let mut rng = XorShift(0x9E37_79B9_7F4A_7C15);
+        for len in 0..100 {
+            let mut list = List::new();
+            for _ in 0..len {
+                list.push(rng.next(50));
+            }
+            let mut expected = to_vec(&list);
+            list.sort();
+            expected.sort();
+            assert_eq!(to_vec(&list), expected);
+        }
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn long_lists() {
+        let n = 1_000_000;
+        let mut rng = XorShift(0x1234_5678_9ABC_DEF1);
+        let mut list = List::new();
+        for _ in 0..n {
+            list.push(rng.next(1u64 << 32));
+        }
+
+        list.sort();
+        assert!(list.iter().zip(list.iter().skip(1)).all(|(a, b)| a <= b));
+        list.reverse();
+        assert!(list.iter().zip(list.iter().skip(1)).all(|(a, b)| a >= b));
+
+        let mut tail = list.split_off(n / 2);
+        assert_eq!(tail.len(), n / 2);
+        list.append(&mut tail);
+        assert_eq!(list.len(), n);
+
+        list.retain(|&x| x % 2 == 0);
+        list.dedup();
+    }
+
+    #[test]
+    fn std_traits() {
+        let mut list: List<i32> = (1..=3).collect();
+        assert_eq!(list.peek(), Some(&1));
+        list.extend(vec![4, 5]);
+        assert_eq!(format!("{:?}", list), "[1, 2, 3, 4, 5]");
+
+        for elem in &mut list {
+            *elem *= 10;
+        }
+        let mut sum = 0;
+        for elem in &list {
+            sum += elem;
+        }
+        assert_eq!(sum, 150);
+
+        let copy = list.clone();
+        assert_eq!(copy, list);
+        assert_eq!(hash_of(&copy), hash_of(&list));
+        assert_eq!(List::<i32>::default(), List::new());
+
+        assert!(list < list_from(&[10, 20, 31]));
+        assert!(list > list_from(&[10, 20, 30]));
+        assert_ne!(hash_of(&list), hash_of(&list_from(&[10, 20, 30])));
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn deep_clone_and_drop() {
+        let list: List<String> = (0..1_000_000).map(|i| i.to_string()).collect();
+        let copy = list.clone();
+        assert_eq!(copy.len(), 1_000_000);
+        assert!(copy == list);
+    }
 }
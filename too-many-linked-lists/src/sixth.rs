@@ -0,0 +1,1189 @@
+use alloc::alloc::{Allocator, Global};
+use alloc::boxed::Box;
+use alloc::format;
+use core::cmp::Ordering;
+use core::fmt::{self, Debug};
+use core::hash::{Hash, Hasher};
+use core::marker::PhantomData;
+use core::mem;
+use core::ptr::NonNull;
+
+use crate::pool::SameAllocator;
+use crate::traits::{Collection, Deque, Queue, Stack};
+use crate::viz::{self, Graph, ToDot};
+
+type Link<T> = Option<NonNull<Node<T>>>;
+
+// Like std's, the allocator lives in the list rather than in every node, and
+// a node is freed by whichever list it ends up in. So the cursor only splices
+// in lists with the same allocator.
+pub struct LinkedList<T, A: Allocator = Global> {
+    front: Link<T>,
+    back: Link<T>,
+    len: usize,
+    alloc: A,
+    _boo: PhantomData<T>,
+}
+
+struct Node<T> {
+    front: Link<T>,
+    back: Link<T>,
+    elem: T,
+}
+
+pub struct Iter<'a, T> {
+    front: Link<T>,
+    back: Link<T>,
+    len: usize,
+    _boo: PhantomData<&'a T>,
+}
+
+pub struct IterMut<'a, T> {
+    front: Link<T>,
+    back: Link<T>,
+    len: usize,
+    _boo: PhantomData<&'a mut T>,
+}
+
+pub struct IntoIter<T, A: Allocator = Global> {
+    list: LinkedList<T, A>,
+}
+
+pub struct CursorMut<'a, T, A: Allocator = Global> {
+    list: &'a mut LinkedList<T, A>,
+    cur: Link<T>,
+    index: Option<usize>,
+}
+
+impl<T> LinkedList<T> {
+    pub fn new() -> Self {
+        Self::new_in(Global)
+    }
+}
+
+impl<T, A: Allocator> LinkedList<T, A> {
+    pub fn new_in(alloc: A) -> Self {
+        Self {
+            front: None,
+            back: None,
+            len: 0,
+            alloc,
+            _boo: PhantomData,
+        }
+    }
+
+    pub fn allocator(&self) -> &A {
+        &self.alloc
+    }
+
+    pub fn push_front(&mut self, elem: T) {
+        unsafe {
+            let new = NonNull::from(Box::leak(Box::new_in(
+                Node {
+                    front: None,
+                    back: None,
+                    elem,
+                },
+                &self.alloc,
+            )));
+
+            if let Some(old) = self.front {
+                (*old.as_ptr()).front = Some(new);
+                (*new.as_ptr()).back = Some(old);
+            } else {
+                self.back = Some(new);
+            }
+
+            self.front = Some(new);
+            self.len += 1;
+        }
+    }
+
+    pub fn push_back(&mut self, elem: T) {
+        unsafe {
+            let new = NonNull::from(Box::leak(Box::new_in(
+                Node {
+                    front: None,
+                    back: None,
+                    elem,
+                },
+                &self.alloc,
+            )));
+
+            if let Some(old) = self.back {
+                (*old.as_ptr()).back = Some(new);
+                (*new.as_ptr()).front = Some(old);
+            } else {
+                self.front = Some(new);
+            }
+
+            self.back = Some(new);
+            self.len += 1;
+        }
+    }
+
+    pub fn pop_front(&mut self) -> Option<T> {
+        unsafe {
+            self.front.map(|node| {
+                let boxed_node = Box::from_raw_in(node.as_ptr(), &self.alloc);
+                let result = boxed_node.elem;
+
+                self.front = boxed_node.back;
+                if let Some(new) = self.front {
+                    (*new.as_ptr()).front = None;
+                } else {
+                    self.back = None;
+                }
+
+                self.len -= 1;
+                result
+            })
+        }
+    }
+
+    pub fn pop_back(&mut self) -> Option<T> {
+        unsafe {
+            self.back.map(|node| {
+                let boxed_node = Box::from_raw_in(node.as_ptr(), &self.alloc);
+                let result = boxed_node.elem;
+
+                self.back = boxed_node.front;
+                if let Some(new) = self.back {
+                    (*new.as_ptr()).back = None;
+                } else {
+                    self.front = None;
+                }
+
+                self.len -= 1;
+                result
+            })
+        }
+    }
+
+    pub fn front(&self) -> Option<&T> {
+        unsafe { self.front.map(|node| &(*node.as_ptr()).elem) }
+    }
+
+    pub fn front_mut(&mut self) -> Option<&mut T> {
+        unsafe { self.front.map(|node| &mut (*node.as_ptr()).elem) }
+    }
+
+    pub fn back(&self) -> Option<&T> {
+        unsafe { self.back.map(|node| &(*node.as_ptr()).elem) }
+    }
+
+    pub fn back_mut(&mut self) -> Option<&mut T> {
+        unsafe { self.back.map(|node| &mut (*node.as_ptr()).elem) }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn clear(&mut self) {
+        while self.pop_front().is_some() {}
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            front: self.front,
+            back: self.back,
+            len: self.len,
+            _boo: PhantomData,
+        }
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        IterMut {
+            front: self.front,
+            back: self.back,
+            len: self.len,
+            _boo: PhantomData,
+        }
+    }
+
+    pub fn cursor_mut(&mut self) -> CursorMut<'_, T, A> {
+        CursorMut {
+            list: self,
+            cur: None,
+            index: None,
+        }
+    }
+}
+
+impl<T, A: Allocator> Drop for LinkedList<T, A> {
+    fn drop(&mut self) {
+        while self.pop_front().is_some() {}
+    }
+}
+
+impl<T, A: Allocator + Default> Default for LinkedList<T, A> {
+    fn default() -> Self {
+        Self::new_in(A::default())
+    }
+}
+
+impl<T: Clone, A: Allocator + Clone> Clone for LinkedList<T, A> {
+    fn clone(&self) -> Self {
+        let mut new_list = Self::new_in(self.alloc.clone());
+        for item in self {
+            new_list.push_back(item.clone());
+        }
+        new_list
+    }
+}
+
+impl<T, A: Allocator> Extend<T> for LinkedList<T, A> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for item in iter {
+            self.push_back(item);
+        }
+    }
+}
+
+impl<T> FromIterator<T> for LinkedList<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut list = Self::new();
+        list.extend(iter);
+        list
+    }
+}
+
+impl<T: Debug, A: Allocator> Debug for LinkedList<T, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self).finish()
+    }
+}
+
+impl<T: PartialEq, A: Allocator> PartialEq for LinkedList<T, A> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.iter().eq(other)
+    }
+}
+
+impl<T: Eq, A: Allocator> Eq for LinkedList<T, A> {}
+
+impl<T: PartialOrd, A: Allocator> PartialOrd for LinkedList<T, A> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.iter().partial_cmp(other)
+    }
+}
+
+impl<T: Ord, A: Allocator> Ord for LinkedList<T, A> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.iter().cmp(other)
+    }
+}
+
+impl<T: Hash, A: Allocator> Hash for LinkedList<T, A> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.len().hash(state);
+        for item in self {
+            item.hash(state);
+        }
+    }
+}
+
+impl<'a, T, A: Allocator> IntoIterator for &'a LinkedList<T, A> {
+    type IntoIter = Iter<'a, T>;
+    type Item = &'a T;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, T, A: Allocator> IntoIterator for &'a mut LinkedList<T, A> {
+    type IntoIter = IterMut<'a, T>;
+    type Item = &'a mut T;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+impl<T, A: Allocator> IntoIterator for LinkedList<T, A> {
+    type IntoIter = IntoIter<T, A>;
+    type Item = T;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter { list: self }
+    }
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.len > 0 {
+            self.front.map(|node| unsafe {
+                self.len -= 1;
+                self.front = (*node.as_ptr()).back;
+                &(*node.as_ptr()).elem
+            })
+        } else {
+            None
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<T> DoubleEndedIterator for Iter<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.len > 0 {
+            self.back.map(|node| unsafe {
+                self.len -= 1;
+                self.back = (*node.as_ptr()).front;
+                &(*node.as_ptr()).elem
+            })
+        } else {
+            None
+        }
+    }
+}
+
+impl<T> ExactSizeIterator for Iter<'_, T> {
+    fn len(&self) -> usize {
+        self.len
+    }
+}
+
+impl<'a, T> Iterator for IterMut<'a, T> {
+    type Item = &'a mut T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.len > 0 {
+            self.front.map(|node| unsafe {
+                self.len -= 1;
+                self.front = (*node.as_ptr()).back;
+                &mut (*node.as_ptr()).elem
+            })
+        } else {
+            None
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<T> DoubleEndedIterator for IterMut<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.len > 0 {
+            self.back.map(|node| unsafe {
+                self.len -= 1;
+                self.back = (*node.as_ptr()).front;
+                &mut (*node.as_ptr()).elem
+            })
+        } else {
+            None
+        }
+    }
+}
+
+impl<T> ExactSizeIterator for IterMut<'_, T> {
+    fn len(&self) -> usize {
+        self.len
+    }
+}
+
+impl<T, A: Allocator> Iterator for IntoIter<T, A> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.list.pop_front()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.list.len, Some(self.list.len))
+    }
+}
+
+impl<T, A: Allocator> DoubleEndedIterator for IntoIter<T, A> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.list.pop_back()
+    }
+}
+
+impl<T, A: Allocator> ExactSizeIterator for IntoIter<T, A> {
+    fn len(&self) -> usize {
+        self.list.len
+    }
+}
+
+// The cursor sits either on an element or on the "ghost" position between the
+// back and the front of the list, which is where it starts.
+impl<T, A: Allocator> CursorMut<'_, T, A> {
+    pub fn index(&self) -> Option<usize> {
+        self.index
+    }
+
+    pub fn move_next(&mut self) {
+        if let Some(cur) = self.cur {
+            unsafe {
+                self.cur = (*cur.as_ptr()).back;
+                if self.cur.is_some() {
+                    *self.index.as_mut().unwrap() += 1;
+                } else {
+                    self.index = None;
+                }
+            }
+        } else if !self.list.is_empty() {
+            self.cur = self.list.front;
+            self.index = Some(0)
+        }
+    }
+
+    pub fn move_prev(&mut self) {
+        if let Some(cur) = self.cur {
+            unsafe {
+                self.cur = (*cur.as_ptr()).front;
+                if self.cur.is_some() {
+                    *self.index.as_mut().unwrap() -= 1;
+                } else {
+                    self.index = None;
+                }
+            }
+        } else if !self.list.is_empty() {
+            self.cur = self.list.back;
+            self.index = Some(self.list.len - 1)
+        }
+    }
+
+    pub fn current(&mut self) -> Option<&mut T> {
+        unsafe { self.cur.map(|node| &mut (*node.as_ptr()).elem) }
+    }
+
+    pub fn peek_next(&mut self) -> Option<&mut T> {
+        unsafe {
+            let next = if let Some(cur) = self.cur {
+                (*cur.as_ptr()).back
+            } else {
+                self.list.front
+            };
+
+            next.map(|node| &mut (*node.as_ptr()).elem)
+        }
+    }
+
+    pub fn peek_prev(&mut self) -> Option<&mut T> {
+        unsafe {
+            let prev = if let Some(cur) = self.cur {
+                (*cur.as_ptr()).front
+            } else {
+                self.list.back
+            };
+
+            prev.map(|node| &mut (*node.as_ptr()).elem)
+        }
+    }
+
+    pub fn remove_current(&mut self) -> Option<T> {
+        let cur = self.cur?;
+
+        unsafe {
+            let boxed_node = Box::from_raw_in(cur.as_ptr(), &self.list.alloc);
+            let prev = boxed_node.front;
+            let next = boxed_node.back;
+
+            match prev {
+                Some(prev) => (*prev.as_ptr()).back = next,
+                None => self.list.front = next,
+            }
+
+            match next {
+                Some(next) => (*next.as_ptr()).front = prev,
+                None => self.list.back = prev,
+            }
+
+            self.list.len -= 1;
+            self.cur = next;
+            if next.is_none() {
+                self.index = None;
+            }
+
+            Some(boxed_node.elem)
+        }
+    }
+
+    // Callers make sure `input`'s nodes can be freed by our allocator.
+    fn splice_nodes_before(&mut self, mut input: LinkedList<T, A>) {
+        unsafe {
+            if input.is_empty() {
+                // Nothing to do
+            } else if let Some(cur) = self.cur {
+                let in_front = input.front.take().unwrap();
+                let in_back = input.back.take().unwrap();
+
+                if let Some(prev) = (*cur.as_ptr()).front {
+                    (*prev.as_ptr()).back = Some(in_front);
+                    (*in_front.as_ptr()).front = Some(prev);
+                } else {
+                    self.list.front = Some(in_front);
+                }
+                (*cur.as_ptr()).front = Some(in_back);
+                (*in_back.as_ptr()).back = Some(cur);
+
+                *self.index.as_mut().unwrap() += input.len;
+            } else if let Some(back) = self.list.back {
+                // We're on the ghost, so "before" is the back of the list
+                let in_front = input.front.take().unwrap();
+                let in_back = input.back.take().unwrap();
+
+                (*back.as_ptr()).back = Some(in_front);
+                (*in_front.as_ptr()).front = Some(back);
+                self.list.back = Some(in_back);
+            } else {
+                // We're empty, become the input
+                mem::swap(self.list, &mut input);
+            }
+
+            self.list.len += input.len;
+            input.len = 0;
+        }
+    }
+
+    fn splice_nodes_after(&mut self, mut input: LinkedList<T, A>) {
+        unsafe {
+            if input.is_empty() {
+                // Nothing to do
+            } else if let Some(cur) = self.cur {
+                let in_front = input.front.take().unwrap();
+                let in_back = input.back.take().unwrap();
+
+                if let Some(next) = (*cur.as_ptr()).back {
+                    (*next.as_ptr()).front = Some(in_back);
+                    (*in_back.as_ptr()).back = Some(next);
+                } else {
+                    self.list.back = Some(in_back);
+                }
+                (*cur.as_ptr()).back = Some(in_front);
+                (*in_front.as_ptr()).front = Some(cur);
+            } else if let Some(front) = self.list.front {
+                // We're on the ghost, so "after" is the front of the list
+                let in_front = input.front.take().unwrap();
+                let in_back = input.back.take().unwrap();
+
+                (*front.as_ptr()).front = Some(in_back);
+                (*in_back.as_ptr()).back = Some(front);
+                self.list.front = Some(in_front);
+            } else {
+                // We're empty, become the input
+                mem::swap(self.list, &mut input);
+            }
+
+            self.list.len += input.len;
+            input.len = 0;
+        }
+    }
+}
+
+// Splicing hands the input's nodes to this list, so both need the same
+// allocator. Panics if they don't have it.
+impl<T, A: SameAllocator> CursorMut<'_, T, A> {
+    pub fn splice_before(&mut self, input: LinkedList<T, A>) {
+        assert!(
+            self.list.alloc.same_as(&input.alloc),
+            "can't move nodes between lists with different allocators"
+        );
+        self.splice_nodes_before(input);
+    }
+
+    pub fn splice_after(&mut self, input: LinkedList<T, A>) {
+        assert!(
+            self.list.alloc.same_as(&input.alloc),
+            "can't move nodes between lists with different allocators"
+        );
+        self.splice_nodes_after(input);
+    }
+}
+
+// These build new lists, each with a clone of the list's allocator.
+impl<T, A: Allocator + Clone> CursorMut<'_, T, A> {
+    pub fn insert_before(&mut self, elem: T) {
+        let mut single = LinkedList::new_in(self.list.alloc.clone());
+        single.push_back(elem);
+        self.splice_nodes_before(single);
+    }
+
+    pub fn insert_after(&mut self, elem: T) {
+        let mut single = LinkedList::new_in(self.list.alloc.clone());
+        single.push_back(elem);
+        self.splice_nodes_after(single);
+    }
+
+    pub fn split_before(&mut self) -> LinkedList<T, A> {
+        if let Some(cur) = self.cur {
+            unsafe {
+                let old_len = self.list.len;
+                let old_idx = self.index.unwrap();
+                let prev = (*cur.as_ptr()).front;
+
+                // At the front there's nothing before the cursor to split off
+                if prev.is_none() {
+                    return LinkedList::new_in(self.list.alloc.clone());
+                }
+
+                let new_len = old_len - old_idx;
+                let new_front = self.cur;
+                let new_back = self.list.back;
+                let new_idx = Some(0);
+
+                let output_len = old_len - new_len;
+                let output_front = self.list.front;
+                let output_back = prev;
+
+                if let Some(prev) = prev {
+                    (*cur.as_ptr()).front = None;
+                    (*prev.as_ptr()).back = None;
+                }
+
+                self.list.len = new_len;
+                self.list.front = new_front;
+                self.list.back = new_back;
+                self.index = new_idx;
+
+                LinkedList {
+                    front: output_front,
+                    back: output_back,
+                    len: output_len,
+                    alloc: self.list.alloc.clone(),
+                    _boo: PhantomData,
+                }
+            }
+        } else {
+            let empty = LinkedList::new_in(self.list.alloc.clone());
+            mem::replace(self.list, empty)
+        }
+    }
+
+    pub fn split_after(&mut self) -> LinkedList<T, A> {
+        if let Some(cur) = self.cur {
+            unsafe {
+                let old_len = self.list.len;
+                let old_idx = self.index.unwrap();
+                let next = (*cur.as_ptr()).back;
+
+                if next.is_none() {
+                    return LinkedList::new_in(self.list.alloc.clone());
+                }
+
+                let new_len = old_idx + 1;
+                let new_back = self.cur;
+                let new_front = self.list.front;
+                let new_idx = Some(old_idx);
+
+                let output_len = old_len - new_len;
+                let output_front = next;
+                let output_back = self.list.back;
+
+                if let Some(next) = next {
+                    (*cur.as_ptr()).back = None;
+                    (*next.as_ptr()).front = None;
+                }
+
+                self.list.len = new_len;
+                self.list.front = new_front;
+                self.list.back = new_back;
+                self.index = new_idx;
+
+                LinkedList {
+                    front: output_front,
+                    back: output_back,
+                    len: output_len,
+                    alloc: self.list.alloc.clone(),
+                    _boo: PhantomData,
+                }
+            }
+        } else {
+            let empty = LinkedList::new_in(self.list.alloc.clone());
+            mem::replace(self.list, empty)
+        }
+    }
+}
+
+unsafe impl<T: Send, A: Allocator + Send> Send for LinkedList<T, A> {}
+unsafe impl<T: Sync, A: Allocator + Sync> Sync for LinkedList<T, A> {}
+
+unsafe impl<T: Sync> Send for Iter<'_, T> {}
+unsafe impl<T: Sync> Sync for Iter<'_, T> {}
+
+unsafe impl<T: Send> Send for IterMut<'_, T> {}
+unsafe impl<T: Sync> Sync for IterMut<'_, T> {}
+
+unsafe impl<T: Send, A: Allocator + Send> Send for CursorMut<'_, T, A> {}
+unsafe impl<T: Sync, A: Allocator + Sync> Sync for CursorMut<'_, T, A> {}
+
+impl<T, A: Allocator> Collection<T> for LinkedList<T, A> {
+    type Ref<'a>
+        = &'a T
+    where
+        Self: 'a;
+
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn clear(&mut self) {
+        LinkedList::clear(self)
+    }
+}
+
+impl<T, A: Allocator> Stack<T> for LinkedList<T, A> {
+    fn push(&mut self, elem: T) {
+        self.push_front(elem)
+    }
+
+    fn pop(&mut self) -> Option<T> {
+        self.pop_front()
+    }
+
+    fn peek(&self) -> Option<&T> {
+        self.front()
+    }
+}
+
+impl<T, A: Allocator> Queue<T> for LinkedList<T, A> {
+    fn push(&mut self, elem: T) {
+        self.push_back(elem)
+    }
+
+    fn pop(&mut self) -> Option<T> {
+        self.pop_front()
+    }
+
+    fn peek(&self) -> Option<&T> {
+        self.front()
+    }
+}
+
+impl<T, A: Allocator> Deque<T> for LinkedList<T, A> {
+    fn push_front(&mut self, elem: T) {
+        LinkedList::push_front(self, elem)
+    }
+
+    fn push_back(&mut self, elem: T) {
+        LinkedList::push_back(self, elem)
+    }
+
+    fn pop_front(&mut self) -> Option<T> {
+        LinkedList::pop_front(self)
+    }
+
+    fn pop_back(&mut self) -> Option<T> {
+        LinkedList::pop_back(self)
+    }
+
+    fn peek_front(&self) -> Option<&T> {
+        self.front()
+    }
+
+    fn peek_back(&self) -> Option<&T> {
+        self.back()
+    }
+}
+
+impl<T: Debug, A: Allocator> ToDot for LinkedList<T, A> {
+    fn write_dot(&self, name: &str, graph: &mut Graph) {
+        let root = graph.root(name);
+        if let Some(back) = self.back {
+            graph.edge(&root, &viz::id(back.as_ptr()), "label=\"back\"");
+        }
+        let mut from = root;
+        let mut attrs = "label=\"front\"";
+        let mut cur = self.front;
+        while let Some(node) = cur {
+            let ptr = node.as_ptr();
+            let node = unsafe { node.as_ref() };
+            graph.node(ptr, &format!("{:?}", node.elem));
+            graph.edge(&from, &viz::id(ptr), attrs);
+            if let Some(prev) = node.front {
+                graph.edge(&viz::id(ptr), &viz::id(prev.as_ptr()), "style=dashed");
+            }
+            from = viz::id(ptr);
+            attrs = "";
+            cur = node.back;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{IntoIter, Iter, LinkedList};
+
+    fn list_from<T: Clone>(v: &[T]) -> LinkedList<T> {
+        v.iter().cloned().collect()
+    }
+
+    // Walks the list in both directions and checks the links agree.
+    fn check_links<T: Eq + std::fmt::Debug>(list: &LinkedList<T>) {
+        let from_front: Vec<_> = list.iter().collect();
+        let mut from_back: Vec<_> = list.iter().rev().collect();
+        from_back.reverse();
+
+        assert_eq!(from_front.len(), list.len());
+        assert_eq!(from_front, from_back);
+    }
+
+    #[test]
+    fn basics_front() {
+        let mut list = LinkedList::new();
+
+        // Try to break an empty list
+        assert_eq!(list.len(), 0);
+        assert_eq!(list.pop_front(), None);
+        assert_eq!(list.len(), 0);
+
+        // Try to break a one item list
+        list.push_front(10);
+        assert_eq!(list.len(), 1);
+        assert_eq!(list.pop_front(), Some(10));
+        assert_eq!(list.len(), 0);
+        assert_eq!(list.pop_front(), None);
+        assert_eq!(list.len(), 0);
+
+        // Mess around
+        list.push_front(10);
+        assert_eq!(list.len(), 1);
+        list.push_front(20);
+        assert_eq!(list.len(), 2);
+        list.push_front(30);
+        assert_eq!(list.len(), 3);
+        assert_eq!(list.pop_front(), Some(30));
+        assert_eq!(list.len(), 2);
+        list.push_front(40);
+        assert_eq!(list.len(), 3);
+        assert_eq!(list.pop_front(), Some(40));
+        assert_eq!(list.len(), 2);
+        assert_eq!(list.pop_front(), Some(20));
+        assert_eq!(list.len(), 1);
+        assert_eq!(list.pop_front(), Some(10));
+        assert_eq!(list.len(), 0);
+        assert_eq!(list.pop_front(), None);
+        assert_eq!(list.len(), 0);
+    }
+
+    #[test]
+    fn basics_back() {
+        let mut list = LinkedList::new();
+        assert_eq!(list.pop_back(), None);
+
+        list.push_back(1);
+        list.push_back(2);
+        list.push_front(0);
+        assert_eq!(list.front(), Some(&0));
+        assert_eq!(list.back(), Some(&2));
+
+        *list.front_mut().unwrap() += 10;
+        *list.back_mut().unwrap() += 10;
+        check_links(&list);
+
+        assert_eq!(list.pop_back(), Some(12));
+        assert_eq!(list.pop_back(), Some(1));
+        assert_eq!(list.pop_back(), Some(10));
+        assert_eq!(list.pop_back(), None);
+        assert!(list.is_empty());
+    }
+
+    #[test]
+    fn iter() {
+        let list = list_from(&[0, 1, 2, 3, 4]);
+
+        let mut iter = list.iter();
+        assert_eq!(iter.len(), 5);
+        assert_eq!(iter.next(), Some(&0));
+        assert_eq!(iter.next_back(), Some(&4));
+        assert_eq!(iter.next(), Some(&1));
+        assert_eq!(iter.next_back(), Some(&3));
+        assert_eq!(iter.next(), Some(&2));
+        assert_eq!(iter.next_back(), None);
+        assert_eq!(iter.next(), None);
+
+        assert_eq!(
+            list.iter().rev().copied().collect::<Vec<_>>(),
+            vec![4, 3, 2, 1, 0]
+        );
+    }
+
+    #[test]
+    fn iter_mut() {
+        let mut list = list_from(&[0, 1, 2, 3]);
+
+        for elem in list.iter_mut() {
+            *elem *= 10;
+        }
+
+        let mut iter = list.iter_mut();
+        assert_eq!(iter.next_back(), Some(&mut 30));
+        assert_eq!(iter.next(), Some(&mut 0));
+        assert_eq!(iter.len(), 2);
+
+        assert_eq!(list, list_from(&[0, 10, 20, 30]));
+    }
+
+    #[test]
+    fn into_iter() {
+        let list = list_from(&[1, 2, 3, 4]);
+
+        let mut iter = list.into_iter();
+        assert_eq!(iter.next(), Some(1));
+        assert_eq!(iter.next_back(), Some(4));
+        assert_eq!(iter.len(), 2);
+        assert_eq!(iter.collect::<Vec<_>>(), vec![2, 3]);
+    }
+
+    #[test]
+    fn traits() {
+        let list = list_from(&[1, 2, 3]);
+        let copy = list.clone();
+
+        assert_eq!(list, copy);
+        assert!(list < list_from(&[1, 2, 4]));
+        assert!(list > list_from(&[1, 2]));
+        assert_eq!(format!("{:?}", list), "[1, 2, 3]");
+        assert_eq!(LinkedList::<i32>::default(), LinkedList::new());
+    }
+
+    #[test]
+    fn cursor_move_peek() {
+        let mut list = list_from(&[1, 2, 3, 4, 5, 6]);
+        let mut cursor = list.cursor_mut();
+
+        // Starts at the ghost
+        assert_eq!(cursor.current(), None);
+        assert_eq!(cursor.index(), None);
+        assert_eq!(cursor.peek_next(), Some(&mut 1));
+        assert_eq!(cursor.peek_prev(), Some(&mut 6));
+
+        cursor.move_next();
+        assert_eq!(cursor.current(), Some(&mut 1));
+        assert_eq!(cursor.peek_next(), Some(&mut 2));
+        assert_eq!(cursor.peek_prev(), None);
+        assert_eq!(cursor.index(), Some(0));
+
+        cursor.move_prev();
+        assert_eq!(cursor.current(), None);
+        assert_eq!(cursor.index(), None);
+
+        cursor.move_prev();
+        assert_eq!(cursor.current(), Some(&mut 6));
+        assert_eq!(cursor.index(), Some(5));
+
+        cursor.move_prev();
+        *cursor.current().unwrap() *= 10;
+        assert_eq!(cursor.index(), Some(4));
+
+        cursor.move_next();
+        cursor.move_next();
+        assert_eq!(cursor.current(), None);
+
+        assert_eq!(list, list_from(&[1, 2, 3, 4, 50, 6]));
+
+        let mut empty = LinkedList::<i32>::new();
+        let mut cursor = empty.cursor_mut();
+        cursor.move_next();
+        assert_eq!(cursor.current(), None);
+        cursor.move_prev();
+        assert_eq!(cursor.current(), None);
+    }
+
+    #[test]
+    fn cursor_insert_remove() {
+        let mut list = list_from(&[1, 2, 3]);
+        let mut cursor = list.cursor_mut();
+
+        // On the ghost, "before" is the back and "after" is the front
+        cursor.insert_before(4);
+        cursor.insert_after(0);
+        assert_eq!(cursor.index(), None);
+
+        cursor.move_next();
+        cursor.move_next();
+        assert_eq!(cursor.current(), Some(&mut 1));
+        assert_eq!(cursor.index(), Some(1));
+
+        cursor.insert_before(10);
+        assert_eq!(cursor.index(), Some(2));
+        cursor.insert_after(11);
+        assert_eq!(cursor.current(), Some(&mut 1));
+
+        assert_eq!(cursor.remove_current(), Some(1));
+        assert_eq!(cursor.current(), Some(&mut 11));
+        assert_eq!(cursor.index(), Some(2));
+
+        cursor.move_prev();
+        cursor.move_prev();
+        assert_eq!(cursor.remove_current(), Some(0));
+        assert_eq!(cursor.current(), Some(&mut 10));
+        assert_eq!(cursor.index(), Some(0));
+
+        cursor.move_prev();
+        cursor.move_prev();
+        assert_eq!(cursor.remove_current(), Some(4));
+        assert_eq!(cursor.current(), None);
+        assert_eq!(cursor.remove_current(), None);
+
+        check_links(&list);
+        assert_eq!(list, list_from(&[10, 11, 2, 3]));
+    }
+
+    #[test]
+    fn cursor_split() {
+        let mut list = list_from(&[1, 2, 3, 4, 5, 6]);
+        let mut cursor = list.cursor_mut();
+
+        cursor.move_next();
+        cursor.move_next();
+        cursor.move_next();
+        assert_eq!(cursor.current(), Some(&mut 3));
+
+        let before = cursor.split_before();
+        assert_eq!(cursor.index(), Some(0));
+        assert_eq!(cursor.current(), Some(&mut 3));
+
+        let after = cursor.split_after();
+        assert_eq!(cursor.index(), Some(0));
+        assert_eq!(cursor.peek_next(), None);
+
+        check_links(&before);
+        check_links(&after);
+        check_links(&list);
+        assert_eq!(before, list_from(&[1, 2]));
+        assert_eq!(list, list_from(&[3]));
+        assert_eq!(after, list_from(&[4, 5, 6]));
+
+        // Splitting on the ghost takes everything
+        let mut cursor = list.cursor_mut();
+        let all = cursor.split_after();
+        assert_eq!(all, list_from(&[3]));
+        assert!(list.is_empty());
+    }
+
+    #[test]
+    fn cursor_split_at_the_ends() {
+        let mut list = list_from(&[1, 2, 3]);
+        let mut cursor = list.cursor_mut();
+
+        // Nothing before the first element, nothing after the last
+        cursor.move_next();
+        let before = cursor.split_before();
+        assert!(before.is_empty());
+        assert_eq!(cursor.index(), Some(0));
+        for _ in 0..2 {
+            cursor.move_next();
+        }
+        let after = cursor.split_after();
+        assert!(after.is_empty());
+        assert_eq!(cursor.index(), Some(2));
+
+        drop(before);
+        drop(after);
+        check_links(&list);
+        assert_eq!(list, list_from(&[1, 2, 3]));
+
+        // A single element is both first and last
+        let mut single = list_from(&[7]);
+        let mut cursor = single.cursor_mut();
+        cursor.move_next();
+        drop(cursor.split_before());
+        drop(cursor.split_after());
+        check_links(&single);
+        assert_eq!(single, list_from(&[7]));
+    }
+
+    #[test]
+    fn cursor_splice() {
+        let mut list = list_from(&[1, 2, 3]);
+        let mut cursor = list.cursor_mut();
+
+        cursor.move_next();
+        cursor.move_next();
+        assert_eq!(cursor.current(), Some(&mut 2));
+
+        cursor.splice_before(list_from(&[10, 11]));
+        assert_eq!(cursor.index(), Some(3));
+        cursor.splice_after(list_from(&[20, 21]));
+        assert_eq!(cursor.current(), Some(&mut 2));
+        cursor.splice_after(LinkedList::new());
+
+        // On the ghost
+        cursor.move_next();
+        cursor.move_next();
+        cursor.move_next();
+        cursor.move_next();
+        assert_eq!(cursor.current(), None);
+        cursor.splice_before(list_from(&[98, 99]));
+        cursor.splice_after(list_from(&[-1, 0]));
+
+        check_links(&list);
+        assert_eq!(list, list_from(&[-1, 0, 1, 10, 11, 2, 20, 21, 3, 98, 99]));
+
+        let mut empty = LinkedList::new();
+        empty.cursor_mut().splice_before(list_from(&[7, 8]));
+        check_links(&empty);
+        assert_eq!(empty, list_from(&[7, 8]));
+    }
+
+    #[test]
+    fn miri_food() {
+        let mut list = list_from(&[1, 2, 3, 4]);
+
+        let mut cursor = list.cursor_mut();
+        cursor.move_next();
+        cursor.move_next();
+        let mut tail = cursor.split_after();
+        tail.push_front(100);
+        let head = cursor.split_before();
+        cursor.splice_after(tail);
+        cursor.splice_before(head);
+        cursor.insert_after(200);
+        cursor.move_next();
+        *cursor.current().unwrap() += 1;
+
+        for elem in list.iter_mut().rev() {
+            *elem += 1;
+        }
+
+        assert_eq!(
+            list.into_iter().collect::<Vec<_>>(),
+            vec![2, 3, 202, 101, 4, 5]
+        );
+    }
+
+    #[test]
+    fn properties() {
+        fn is_send<T: Send>() {}
+        fn is_sync<T: Sync>() {}
+
+        is_send::<LinkedList<i32>>();
+        is_sync::<LinkedList<i32>>();
+
+        is_send::<IntoIter<i32>>();
+        is_sync::<IntoIter<i32>>();
+
+        is_send::<Iter<i32>>();
+        is_sync::<Iter<i32>>();
+
+        fn linked_list_covariant<'a, T>(x: LinkedList<&'static T>) -> LinkedList<&'a T> {
+            x
+        }
+        fn iter_covariant<'i, 'a, T>(x: Iter<'i, &'static T>) -> Iter<'i, &'a T> {
+            x
+        }
+        fn into_iter_covariant<'a, T>(x: IntoIter<&'static T>) -> IntoIter<&'a T> {
+            x
+        }
+
+        let _ = linked_list_covariant::<i32>;
+        let _ = iter_covariant::<i32>;
+        let _ = into_iter_covariant::<i32>;
+    }
+}
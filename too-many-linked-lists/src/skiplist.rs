@@ -0,0 +1,468 @@
+use alloc::boxed::Box;
+use core::borrow::Borrow;
+use core::fmt::{self, Debug};
+use core::marker::PhantomData;
+use core::mem;
+use core::ops::{Bound, RangeBounds};
+use core::ptr::NonNull;
+
+#[cfg(feature = "std")]
+pub mod sync;
+
+// Enough levels for about 2^32 entries before searches start to degrade.
+const MAX_HEIGHT: usize = 32;
+
+// Each level is kept with probability 1/2. `seed` only has to differ between
+// calls; it gets scrambled with SplitMix64 first.
+fn random_height(seed: u64) -> usize {
+    let mut z = seed.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^= z >> 31;
+    (z.trailing_ones() as usize + 1).min(MAX_HEIGHT)
+}
+
+type Link<K, V> = Option<NonNull<Node<K, V>>>;
+
+// Levels at or above `height` are all empty. A `None` position in the
+// helpers below stands for the head, which sits before every node.
+pub struct SkipMap<K, V> {
+    head: [Link<K, V>; MAX_HEIGHT],
+    height: usize,
+    len: usize,
+    seed: u64,
+    _boo: PhantomData<Box<Node<K, V>>>,
+}
+
+struct Node<K, V> {
+    key: K,
+    value: V,
+    next: Box<[Link<K, V>]>,
+}
+
+pub struct Iter<'a, K, V> {
+    next: Link<K, V>,
+    end: Link<K, V>,
+    _boo: PhantomData<&'a Node<K, V>>,
+}
+
+impl<K, V> SkipMap<K, V> {
+    pub fn new() -> Self {
+        SkipMap {
+            head: [None; MAX_HEIGHT],
+            height: 0,
+            len: 0,
+            seed: 0,
+            _boo: PhantomData,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn clear(&mut self) {
+        let mut cur = self.head[0];
+        while let Some(node) = cur {
+            let node = unsafe { Box::from_raw(node.as_ptr()) };
+            cur = node.next[0];
+        }
+        self.head = [None; MAX_HEIGHT];
+        self.height = 0;
+        self.len = 0;
+    }
+
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        Iter {
+            next: self.head[0],
+            end: None,
+            _boo: PhantomData,
+        }
+    }
+
+    pub fn first(&self) -> Option<(&K, &V)> {
+        self.head[0].map(|node| unsafe { entry(node) })
+    }
+
+    pub fn last(&self) -> Option<(&K, &V)> {
+        self.preds(|_| false)[0].map(|node| unsafe { entry(node) })
+    }
+
+    fn next(&self, at: Link<K, V>, level: usize) -> Link<K, V> {
+        match at {
+            None => self.head[level],
+            Some(node) => unsafe { (&(*node.as_ptr()).next)[level] },
+        }
+    }
+
+    fn set_next(&mut self, at: Link<K, V>, level: usize, to: Link<K, V>) {
+        match at {
+            None => self.head[level] = to,
+            Some(node) => unsafe { (&mut (*node.as_ptr()).next)[level] = to },
+        }
+    }
+
+    // For every level, the last position whose successor is either missing
+    // or satisfies `past`. `past` must be monotonic in the key order.
+    fn preds<F>(&self, mut past: F) -> [Link<K, V>; MAX_HEIGHT]
+    where
+        F: FnMut(&K) -> bool,
+    {
+        let mut preds = [None; MAX_HEIGHT];
+        let mut at = None;
+        for level in (0..self.height).rev() {
+            while let Some(next) = self.next(at, level) {
+                if past(unsafe { &(*next.as_ptr()).key }) {
+                    break;
+                }
+                at = Some(next);
+            }
+            preds[level] = at;
+        }
+        preds
+    }
+
+    // The first node satisfying `past`.
+    fn first_where<F>(&self, past: F) -> Link<K, V>
+    where
+        F: FnMut(&K) -> bool,
+    {
+        self.next(self.preds(past)[0], 0)
+    }
+}
+
+impl<K: Ord, V> SkipMap<K, V> {
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.find(key).is_some()
+    }
+
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.find(key)
+            .map(|node| unsafe { &(*node.as_ptr()).value })
+    }
+
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.find(key)
+            .map(|node| unsafe { &mut (*node.as_ptr()).value })
+    }
+
+    // Returns the previous value if `key` was already present, in which case
+    // the stored key is left as it was.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let preds = self.preds(|k| *k >= key);
+        if let Some(node) = self.next(preds[0], 0) {
+            let node = unsafe { &mut *node.as_ptr() };
+            if node.key == key {
+                return Some(mem::replace(&mut node.value, value));
+            }
+        }
+
+        self.seed = self.seed.wrapping_add(1);
+        let height = random_height(self.seed);
+        self.height = self.height.max(height);
+
+        let next = (0..height).map(|level| self.next(preds[level], level));
+        let node = NonNull::from(Box::leak(Box::new(Node {
+            key,
+            value,
+            next: next.collect(),
+        })));
+        for (level, &pred) in preds.iter().enumerate().take(height) {
+            self.set_next(pred, level, Some(node));
+        }
+
+        self.len += 1;
+        None
+    }
+
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.remove_entry(key).map(|(_, value)| value)
+    }
+
+    pub fn remove_entry<Q>(&mut self, key: &Q) -> Option<(K, V)>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let preds = self.preds(|k| k.borrow() >= key);
+        let node = self
+            .next(preds[0], 0)
+            .filter(|node| unsafe { (*node.as_ptr()).key.borrow() == key })?;
+
+        let node = unsafe { Box::from_raw(node.as_ptr()) };
+        for (level, &next) in node.next.iter().enumerate() {
+            self.set_next(preds[level], level, next);
+        }
+        while self.height > 0 && self.head[self.height - 1].is_none() {
+            self.height -= 1;
+        }
+
+        self.len -= 1;
+        Some((node.key, node.value))
+    }
+
+    // Entries with keys in `range`, in order.
+    pub fn range<Q, R>(&self, range: R) -> Iter<'_, K, V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+        R: RangeBounds<Q>,
+    {
+        let next = match range.start_bound() {
+            Bound::Included(start) => self.first_where(|k| k.borrow() >= start),
+            Bound::Excluded(start) => self.first_where(|k| k.borrow() > start),
+            Bound::Unbounded => self.head[0],
+        };
+        let end = match range.end_bound() {
+            Bound::Included(end) => self.first_where(|k| k.borrow() > end),
+            Bound::Excluded(end) => self.first_where(|k| k.borrow() >= end),
+            Bound::Unbounded => None,
+        };
+
+        // An empty or inverted range has its start at or after its end.
+        let next = next.filter(|node| range.contains(unsafe { (*node.as_ptr()).key.borrow() }));
+        Iter {
+            next,
+            end,
+            _boo: PhantomData,
+        }
+    }
+
+    fn find<Q>(&self, key: &Q) -> Link<K, V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.first_where(|k| k.borrow() >= key)
+            .filter(|node| unsafe { (*node.as_ptr()).key.borrow() == key })
+    }
+}
+
+unsafe fn entry<'a, K, V>(node: NonNull<Node<K, V>>) -> (&'a K, &'a V) {
+    let node = &*node.as_ptr();
+    (&node.key, &node.value)
+}
+
+impl<K, V> Drop for SkipMap<K, V> {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+impl<K, V> Default for SkipMap<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Ord + Clone, V: Clone> Clone for SkipMap<K, V> {
+    fn clone(&self) -> Self {
+        self.iter().map(|(k, v)| (k.clone(), v.clone())).collect()
+    }
+}
+
+impl<K: Debug, V: Debug> Debug for SkipMap<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<K: Ord, V> Extend<(K, V)> for SkipMap<K, V> {
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        for (key, value) in iter {
+            self.insert(key, value);
+        }
+    }
+}
+
+impl<K: Ord, V> FromIterator<(K, V)> for SkipMap<K, V> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut map = SkipMap::new();
+        map.extend(iter);
+        map
+    }
+}
+
+impl<'a, K, V> IntoIterator for &'a SkipMap<K, V> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.next.filter(|&node| Some(node) != self.end)?;
+        unsafe {
+            self.next = (&(*node.as_ptr()).next)[0];
+            Some(entry(node))
+        }
+    }
+}
+
+unsafe impl<K: Send, V: Send> Send for SkipMap<K, V> {}
+unsafe impl<K: Sync, V: Sync> Sync for SkipMap<K, V> {}
+
+unsafe impl<K: Sync, V: Sync> Send for Iter<'_, K, V> {}
+unsafe impl<K: Sync, V: Sync> Sync for Iter<'_, K, V> {}
+
+#[cfg(test)]
+mod tests {
+    use super::SkipMap;
+    use crate::test_util::XorShift;
+    use std::collections::BTreeMap;
+    use std::ops::Bound;
+
+    fn keys(map: &SkipMap<i32, i32>) -> Vec<i32> {
+        map.iter().map(|(&k, _)| k).collect()
+    }
+
+    #[test]
+    fn basics() {
+        let mut map = SkipMap::new();
+        assert!(map.is_empty());
+        assert_eq!(map.get(&1), None);
+        assert_eq!(map.first(), None);
+        assert_eq!(map.last(), None);
+
+        for key in [5, 1, 9, 3, 7] {
+            assert_eq!(map.insert(key, key * 10), None);
+        }
+        assert_eq!(map.len(), 5);
+        assert_eq!(keys(&map), vec![1, 3, 5, 7, 9]);
+        assert_eq!(map.first(), Some((&1, &10)));
+        assert_eq!(map.last(), Some((&9, &90)));
+
+        assert_eq!(map.insert(3, 33), Some(30));
+        assert_eq!(map.get(&3), Some(&33));
+        *map.get_mut(&7).unwrap() += 1;
+        assert_eq!(map.get(&7), Some(&71));
+        assert!(map.contains_key(&9));
+        assert!(!map.contains_key(&4));
+
+        assert_eq!(map.remove(&4), None);
+        assert_eq!(map.remove(&1), Some(10));
+        assert_eq!(map.remove_entry(&9), Some((9, 90)));
+        assert_eq!(keys(&map), vec![3, 5, 7]);
+        assert_eq!(map.first(), Some((&3, &33)));
+        assert_eq!(map.last(), Some((&7, &71)));
+
+        map.clear();
+        assert!(map.is_empty());
+        assert_eq!(map.iter().next(), None);
+        map.insert(1, 1);
+        assert_eq!(keys(&map), vec![1]);
+    }
+
+    #[test]
+    fn range() {
+        let map: SkipMap<i32, i32> = (0..10).map(|k| (k * 2, k)).collect();
+        let range =
+            |r: (Bound<i32>, Bound<i32>)| -> Vec<i32> { map.range(r).map(|(&k, _)| k).collect() };
+
+        assert_eq!(
+            map.range(3..9).map(|(&k, _)| k).collect::<Vec<_>>(),
+            [4, 6, 8]
+        );
+        assert_eq!(
+            map.range(4..=8).map(|(&k, _)| k).collect::<Vec<_>>(),
+            [4, 6, 8]
+        );
+        assert_eq!(map.range(..3).map(|(&k, _)| k).collect::<Vec<_>>(), [0, 2]);
+        assert_eq!(
+            map.range(15..).map(|(&k, _)| k).collect::<Vec<_>>(),
+            [16, 18]
+        );
+        assert_eq!(map.range(..).count(), 10);
+        assert_eq!(range((Bound::Excluded(4), Bound::Excluded(10))), vec![6, 8]);
+        assert_eq!(
+            range((Bound::Excluded(4), Bound::Included(10))),
+            vec![6, 8, 10]
+        );
+
+        assert_eq!(map.range(5..5).count(), 0);
+        assert!(range((Bound::Included(7), Bound::Excluded(3))).is_empty());
+        assert_eq!(map.range(19..).count(), 0);
+        assert_eq!(map.range(..0).count(), 0);
+    }
+
+    #[test]
+    fn borrowed_keys() {
+        let mut map = SkipMap::new();
+        map.insert(String::from("b"), 2);
+        map.insert(String::from("a"), 1);
+        assert_eq!(map.get("a"), Some(&1));
+        assert_eq!(
+            map.range::<str, _>((Bound::Included("a"), Bound::Excluded("b")))
+                .count(),
+            1
+        );
+        assert_eq!(map.remove("b"), Some(2));
+        assert_eq!(format!("{:?}", map), r#"{"a": 1}"#);
+    }
+
+    #[test]
+    fn matches_btreemap() {
+        let iterations = if cfg!(miri) { 500 } else { 50_000 };
+        let mut rng = XorShift(0x2545_F491_4F6C_DD1D);
+        let mut map = SkipMap::new();
+        let mut model = BTreeMap::new();
+
+        for i in 0..iterations {
+            let key = rng.next(500);
+            match rng.next(10) {
+                0..=3 => assert_eq!(map.insert(key, i), model.insert(key, i)),
+                4..=6 => assert_eq!(map.remove(&key), model.remove(&key)),
+                7 => assert_eq!(map.get(&key), model.get(&key)),
+                8 => {
+                    assert_eq!(map.first(), model.first_key_value());
+                    assert_eq!(map.last(), model.last_key_value());
+                }
+                _ => {
+                    let end = key + rng.next(100);
+                    assert!(map.range(key..end).eq(model.range(key..end)));
+                }
+            }
+            assert_eq!(map.len(), model.len());
+        }
+        assert!(map.iter().eq(model.iter()));
+        assert!(map.clone().iter().eq(model.iter()));
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn large_map() {
+        let mut map: SkipMap<u64, u64> = (0..1_000_000).rev().map(|k| (k, k)).collect();
+        assert_eq!(map.len(), 1_000_000);
+        assert_eq!(map.get(&123_456), Some(&123_456));
+        assert_eq!(map.range(999_990..).count(), 10);
+        for k in (0..1_000_000).step_by(2) {
+            map.remove(&k);
+        }
+        assert_eq!(map.first(), Some((&1, &1)));
+    }
+}
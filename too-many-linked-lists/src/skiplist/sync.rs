@@ -0,0 +1,603 @@
+use std::borrow::Borrow;
+use std::fmt::{self, Debug};
+use std::hint;
+use std::ops::{Bound, RangeBounds};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use super::{random_height, MAX_HEIGHT};
+
+type Link<K, V> = Option<Arc<Node<K, V>>>;
+
+// The lazy skip list from Herlihy and Shavit's "The Art of Multiprocessor
+// Programming". Lookups never take a node lock; each link sits behind its own
+// mutex, which is only held long enough to clone the `Arc` out. Writers lock
+// only the predecessors they're about to relink (plus the victim, for
+// `remove`), always in decreasing key order, so they can't deadlock.
+//
+// A node is logically in the map once `fully_linked` is set and leaves it the
+// moment `marked` is set; the physical (un)linking around those points is
+// only ever done under the locks. Nodes are reference counted, so a reader
+// that's still standing on an unlinked node can keep following its links.
+pub struct SkipMap<K, V> {
+    head: Arc<Node<K, V>>,
+    height: AtomicUsize,
+    len: AtomicUsize,
+    seed: AtomicU64,
+}
+
+struct Node<K, V> {
+    // `None` only for the head.
+    key: Option<K>,
+    // Taken by whoever removes the node.
+    value: Mutex<Option<V>>,
+    next: Box<[Mutex<Link<K, V>>]>,
+    lock: Mutex<()>,
+    marked: AtomicBool,
+    fully_linked: AtomicBool,
+}
+
+pub struct Iter<'a, K, V> {
+    next: Link<K, V>,
+    end: Bound<K>,
+    _map: &'a SkipMap<K, V>,
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap()
+}
+
+impl<K, V> Node<K, V> {
+    fn new(key: Option<K>, value: Option<V>, next: impl Iterator<Item = Link<K, V>>) -> Self {
+        Node {
+            key,
+            value: Mutex::new(value),
+            next: next.map(Mutex::new).collect(),
+            lock: Mutex::new(()),
+            marked: AtomicBool::new(false),
+            fully_linked: AtomicBool::new(false),
+        }
+    }
+
+    fn key(&self) -> &K {
+        self.key.as_ref().unwrap()
+    }
+
+    fn next(&self, level: usize) -> Link<K, V> {
+        lock(&self.next[level]).clone()
+    }
+
+    fn height(&self) -> usize {
+        self.next.len()
+    }
+
+    fn is_live(&self) -> bool {
+        self.fully_linked.load(Ordering::Acquire) && !self.marked.load(Ordering::Acquire)
+    }
+}
+
+// Unlinked nodes keep pointing at their successors, so a long run of them can
+// end up owning each other. Tear those chains down iteratively.
+impl<K, V> Drop for Node<K, V> {
+    fn drop(&mut self) {
+        fn take_links<K, V>(node: &mut Node<K, V>, stack: &mut Vec<Arc<Node<K, V>>>) {
+            for link in node.next.iter_mut() {
+                stack.extend(link.get_mut().unwrap_or_else(|e| e.into_inner()).take());
+            }
+        }
+
+        let mut stack = Vec::new();
+        take_links(self, &mut stack);
+        // `into_inner` rather than `try_unwrap`: when two threads drop the
+        // last handles to a node at once, one of them is sure to get it
+        while let Some(node) = stack.pop() {
+            if let Some(mut node) = Arc::into_inner(node) {
+                take_links(&mut node, &mut stack);
+            }
+        }
+    }
+}
+
+impl<K, V> SkipMap<K, V> {
+    pub fn new() -> Self {
+        SkipMap {
+            head: Arc::new(Node::new(None, None, (0..MAX_HEIGHT).map(|_| None))),
+            height: AtomicUsize::new(1),
+            len: AtomicUsize::new(0),
+            seed: AtomicU64::new(0),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len.load(Ordering::Relaxed)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<K: Ord, V> SkipMap<K, V> {
+    // Fills in the predecessor and successor of `key` on every level and
+    // returns the highest level `key` itself was found on.
+    fn find<Q>(
+        &self,
+        key: &Q,
+        preds: &mut Vec<Arc<Node<K, V>>>,
+        succs: &mut Vec<Link<K, V>>,
+    ) -> Option<usize>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        preds.clear();
+        preds.resize(MAX_HEIGHT, self.head.clone());
+        succs.clear();
+        succs.resize(MAX_HEIGHT, None);
+
+        let mut found = None;
+        let mut pred = self.head.clone();
+        for level in (0..self.height.load(Ordering::Acquire)).rev() {
+            let mut cur = pred.next(level);
+            while let Some(node) = cur.clone().filter(|node| node.key().borrow() < key) {
+                cur = node.next(level);
+                pred = node;
+            }
+            if found.is_none() && cur.as_ref().is_some_and(|node| node.key().borrow() == key) {
+                found = Some(level);
+            }
+            preds[level] = pred.clone();
+            succs[level] = cur;
+        }
+        found
+    }
+
+    // The first node on level 0 whose key satisfies `past`, which must be
+    // monotonic in the key order.
+    fn first_where<F>(&self, mut past: F) -> Link<K, V>
+    where
+        F: FnMut(&K) -> bool,
+    {
+        let mut pred = self.head.clone();
+        let mut cur = None;
+        for level in (0..self.height.load(Ordering::Acquire)).rev() {
+            cur = pred.next(level);
+            while let Some(node) = cur.clone().filter(|node| !past(node.key())) {
+                cur = node.next(level);
+                pred = node;
+            }
+        }
+        cur
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.first_where(|k| k.borrow() >= key)
+            .is_some_and(|node| node.key().borrow() == key && node.is_live())
+    }
+
+    pub fn get<Q>(&self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+        V: Clone,
+    {
+        self.first_where(|k| k.borrow() >= key)
+            .filter(|node| node.key().borrow() == key && node.is_live())
+            .and_then(|node| lock(&node.value).clone())
+    }
+
+    // Returns the previous value if `key` was already present.
+    pub fn insert(&self, key: K, value: V) -> Option<V> {
+        let height = random_height(self.seed.fetch_add(1, Ordering::Relaxed));
+        let mut preds = Vec::with_capacity(MAX_HEIGHT);
+        let mut succs = Vec::with_capacity(MAX_HEIGHT);
+        let mut value = Some(value);
+
+        loop {
+            if let Some(level) = self.find(&key, &mut preds, &mut succs) {
+                let node = succs[level].clone().unwrap();
+                if !node.marked.load(Ordering::Acquire) {
+                    while !node.fully_linked.load(Ordering::Acquire) {
+                        hint::spin_loop();
+                    }
+                    let mut slot = lock(&node.value);
+                    if !node.marked.load(Ordering::Acquire) {
+                        return slot.replace(value.take().unwrap());
+                    }
+                }
+                // Being removed; wait for it to be gone and try again.
+                continue;
+            }
+
+            let guards = match self.lock_preds(&preds, &succs, height) {
+                Some(guards) => guards,
+                None => continue,
+            };
+            // Linking in front of a node that's on its way out would leave us
+            // pointing at it once it's gone.
+            if succs
+                .iter()
+                .take(height)
+                .flatten()
+                .any(|succ| succ.marked.load(Ordering::Acquire))
+            {
+                continue;
+            }
+
+            let node = Arc::new(Node::new(
+                Some(key),
+                value.take(),
+                succs.iter().take(height).cloned(),
+            ));
+            for (level, pred) in preds.iter().enumerate().take(height) {
+                *lock(&pred.next[level]) = Some(node.clone());
+            }
+            // Raised before the node counts as inserted, so whoever sees it
+            // fully linked also searches all of its levels.
+            self.height.fetch_max(height, Ordering::AcqRel);
+            node.fully_linked.store(true, Ordering::Release);
+            self.len.fetch_add(1, Ordering::Relaxed);
+            drop(guards);
+            return None;
+        }
+    }
+
+    pub fn remove<Q>(&self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let mut preds = Vec::with_capacity(MAX_HEIGHT);
+        let mut succs = Vec::with_capacity(MAX_HEIGHT);
+
+        // A node that isn't fully linked yet is treated as not inserted yet.
+        let level = self.find(key, &mut preds, &mut succs)?;
+        let victim = succs[level].clone().unwrap();
+        if !victim.is_live() {
+            return None;
+        }
+
+        let _victim_guard = lock(&victim.lock);
+        if victim.marked.swap(true, Ordering::AcqRel) {
+            return None;
+        }
+
+        // The victim is ours now. Its successors can't change while we hold its
+        // lock, so all that's left is waiting for stable predecessors.
+        loop {
+            self.find(key, &mut preds, &mut succs);
+            let is_victim =
+                |succ: &Link<K, V>| succ.as_ref().is_some_and(|s| Arc::ptr_eq(s, &victim));
+            if !succs[..victim.height()].iter().all(is_victim) {
+                continue;
+            }
+            let guards = match self.lock_preds(&preds, &succs, victim.height()) {
+                Some(guards) => guards,
+                None => continue,
+            };
+            for level in (0..victim.height()).rev() {
+                *lock(&preds[level].next[level]) = victim.next(level);
+            }
+            drop(guards);
+
+            self.len.fetch_sub(1, Ordering::Relaxed);
+            return lock(&victim.value).take();
+        }
+    }
+
+    // Locks the distinct predecessors on levels `0..height`, bottom up, and
+    // checks they're still live and still point at the expected successors.
+    // `None` means something changed and the caller should search again.
+    fn lock_preds<'a>(
+        &self,
+        preds: &'a [Arc<Node<K, V>>],
+        succs: &[Link<K, V>],
+        height: usize,
+    ) -> Option<Vec<MutexGuard<'a, ()>>> {
+        let mut guards = Vec::with_capacity(height);
+        for level in 0..height {
+            let pred = &preds[level];
+            if level == 0 || !Arc::ptr_eq(pred, &preds[level - 1]) {
+                guards.push(lock(&pred.lock));
+            }
+
+            let succ = &succs[level];
+            let still_linked = match (&*lock(&pred.next[level]), succ) {
+                (Some(next), Some(succ)) => Arc::ptr_eq(next, succ),
+                (None, None) => true,
+                _ => false,
+            };
+            if pred.marked.load(Ordering::Acquire) || !still_linked {
+                return None;
+            }
+        }
+        Some(guards)
+    }
+
+    // Entries are cloned out as the iterator goes. Concurrent changes may or
+    // may not show up, but every entry yielded was in the map at some point
+    // during the iteration, and keys always come out in order.
+    pub fn range<R>(&self, range: R) -> Iter<'_, K, V>
+    where
+        R: RangeBounds<K>,
+        K: Clone,
+    {
+        let next = match range.start_bound() {
+            Bound::Included(start) => self.first_where(|k| k >= start),
+            Bound::Excluded(start) => self.first_where(|k| k > start),
+            Bound::Unbounded => self.head.next(0),
+        };
+        Iter {
+            next,
+            end: range.end_bound().cloned(),
+            _map: self,
+        }
+    }
+
+    pub fn iter(&self) -> Iter<'_, K, V>
+    where
+        K: Clone,
+    {
+        self.range(..)
+    }
+
+    pub fn first(&self) -> Option<(K, V)>
+    where
+        K: Clone,
+        V: Clone,
+    {
+        self.iter().next()
+    }
+
+    pub fn last(&self) -> Option<(K, V)>
+    where
+        K: Clone,
+        V: Clone,
+    {
+        loop {
+            let mut last = self.head.clone();
+            for level in (0..self.height.load(Ordering::Acquire)).rev() {
+                while let Some(next) = last.next(level) {
+                    last = next;
+                }
+            }
+
+            if Arc::ptr_eq(&last, &self.head) {
+                return None;
+            }
+            // Whoever is linking or unlinking it holds the locks and won't be
+            // long.
+            if !last.is_live() {
+                hint::spin_loop();
+                continue;
+            }
+            let value = lock(&last.value).clone();
+            if let Some(value) = value {
+                return Some((last.key().clone(), value));
+            }
+        }
+    }
+}
+
+impl<K, V> Default for SkipMap<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Ord + Clone + Debug, V: Clone + Debug> Debug for SkipMap<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<K: Ord, V> FromIterator<(K, V)> for SkipMap<K, V> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let map = SkipMap::new();
+        for (key, value) in iter {
+            map.insert(key, value);
+        }
+        map
+    }
+}
+
+impl<K: Ord + Clone, V: Clone> Iterator for Iter<'_, K, V> {
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(node) = self.next.take() {
+            let in_range = match &self.end {
+                Bound::Included(end) => node.key() <= end,
+                Bound::Excluded(end) => node.key() < end,
+                Bound::Unbounded => true,
+            };
+            if !in_range {
+                return None;
+            }
+
+            self.next = node.next(0);
+            if node.is_live() {
+                if let Some(value) = lock(&node.value).clone() {
+                    return Some((node.key().clone(), value));
+                }
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SkipMap;
+    use crate::test_util::XorShift;
+    use std::collections::BTreeMap;
+    use std::sync::Arc;
+    use std::thread;
+
+    #[test]
+    fn basics() {
+        let map = SkipMap::new();
+        assert!(map.is_empty());
+        assert_eq!(map.first(), None);
+        assert_eq!(map.last(), None);
+
+        for key in [5, 1, 9, 3, 7] {
+            assert_eq!(map.insert(key, key * 10), None);
+        }
+        assert_eq!(map.len(), 5);
+        assert_eq!(map.insert(3, 33), Some(30));
+        assert_eq!(map.get(&3), Some(33));
+        assert_eq!(map.get(&4), None);
+        assert!(map.contains_key(&9));
+
+        assert_eq!(map.first(), Some((1, 10)));
+        assert_eq!(map.last(), Some((9, 90)));
+        assert_eq!(
+            map.range(2..8).map(|(k, _)| k).collect::<Vec<_>>(),
+            [3, 5, 7]
+        );
+        assert_eq!(map.range(3..=5).count(), 2);
+
+        assert_eq!(map.remove(&9), Some(90));
+        assert_eq!(map.remove(&9), None);
+        assert_eq!(map.last(), Some((7, 70)));
+        assert_eq!(format!("{:?}", map), "{1: 10, 3: 33, 5: 50, 7: 70}");
+    }
+
+    #[test]
+    fn matches_btreemap() {
+        let iterations = if cfg!(miri) { 300 } else { 20_000 };
+        let mut rng = XorShift(0x9E37_79B9_7F4A_7C15);
+        let map = SkipMap::new();
+        let mut model = BTreeMap::new();
+
+        for i in 0..iterations {
+            let key = rng.next(300);
+            match rng.next(10) {
+                0..=3 => assert_eq!(map.insert(key, i), model.insert(key, i)),
+                4..=6 => assert_eq!(map.remove(&key), model.remove(&key)),
+                7 => assert_eq!(map.get(&key), model.get(&key).copied()),
+                8 => {
+                    let first = model.first_key_value().map(|(&k, &v)| (k, v));
+                    let last = model.last_key_value().map(|(&k, &v)| (k, v));
+                    assert_eq!(map.first(), first);
+                    assert_eq!(map.last(), last);
+                }
+                _ => {
+                    let end = key + rng.next(50);
+                    let expected = model.range(key..end).map(|(&k, &v)| (k, v));
+                    assert!(map.range(key..end).eq(expected));
+                }
+            }
+            assert_eq!(map.len(), model.len());
+        }
+        assert!(map.iter().eq(model.into_iter()));
+    }
+
+    #[test]
+    fn send_sync() {
+        fn is_send_sync<T: Send + Sync>() {}
+        is_send_sync::<SkipMap<String, Vec<u8>>>();
+    }
+
+    // Every thread owns its own keys, so the final contents are known
+    // exactly even though the threads' operations interleave.
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn concurrent_disjoint_writers() {
+        const THREADS: u64 = 8;
+        const PER_THREAD: u64 = 5_000;
+        let map = Arc::new(SkipMap::new());
+
+        let handles: Vec<_> = (0..THREADS)
+            .map(|t| {
+                let map = map.clone();
+                thread::spawn(move || {
+                    let mut rng = XorShift(t + 1);
+                    let mut model = BTreeMap::new();
+                    for i in 0..PER_THREAD * 4 {
+                        let key = rng.next(PER_THREAD) * THREADS + t;
+                        if rng.next(3) == 0 {
+                            assert_eq!(map.remove(&key), model.remove(&key));
+                        } else {
+                            assert_eq!(map.insert(key, i), model.insert(key, i));
+                        }
+                    }
+                    model
+                })
+            })
+            .collect();
+
+        let mut expected = BTreeMap::new();
+        for handle in handles {
+            expected.extend(handle.join().unwrap());
+        }
+        assert_eq!(map.len(), expected.len());
+        assert!(map.iter().eq(expected.into_iter()));
+    }
+
+    // Everyone fights over the same few keys. Each key's final value has to
+    // be whatever was last written to it, and readers must always see keys
+    // in strictly increasing order.
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn concurrent_contended() {
+        const KEYS: u64 = 64;
+        let map = Arc::new(SkipMap::new());
+
+        let writers: Vec<_> = (0..6)
+            .map(|t| {
+                let map = map.clone();
+                thread::spawn(move || {
+                    let mut rng = XorShift(t + 100);
+                    for i in 0..20_000 {
+                        let key = rng.next(KEYS);
+                        if rng.next(2) == 0 {
+                            map.insert(key, i);
+                        } else {
+                            map.remove(&key);
+                        }
+                    }
+                })
+            })
+            .collect();
+
+        let readers: Vec<_> = (0..2)
+            .map(|_| {
+                let map = map.clone();
+                thread::spawn(move || {
+                    for _ in 0..500 {
+                        let keys: Vec<u64> = map.iter().map(|(k, _)| k).collect();
+                        assert!(keys.windows(2).all(|w| w[0] < w[1]));
+                        assert!(keys.len() <= KEYS as usize);
+                    }
+                })
+            })
+            .collect();
+
+        for handle in writers.into_iter().chain(readers) {
+            handle.join().unwrap();
+        }
+
+        let entries: Vec<_> = map.iter().collect();
+        assert_eq!(entries.len(), map.len());
+        for (key, value) in entries {
+            assert_eq!(map.get(&key), Some(value));
+        }
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn drop_large_map() {
+        let map: SkipMap<u64, u64> = (0..100_000).map(|k| (k, k)).collect();
+        assert_eq!(map.len(), 100_000);
+        for k in 0..50_000 {
+            map.remove(&k);
+        }
+        assert_eq!(map.first(), Some((50_000, 50_000)));
+    }
+}
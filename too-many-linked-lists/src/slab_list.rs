@@ -0,0 +1,531 @@
+use alloc::vec::Vec;
+use core::mem;
+
+use crate::traits::{Collection, Deque, Queue, Stack};
+
+type Link = Option<usize>;
+
+// Refers to one element of a `List`. Every time a slot is freed its generation
+// is bumped, so a handle to a removed element can never alias whatever gets
+// stored in that slot later.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Handle {
+    index: usize,
+    generation: u64,
+}
+
+pub struct List<T> {
+    slots: Vec<Slot<T>>,
+    head: Link,
+    tail: Link,
+    free: Link,
+    len: usize,
+}
+
+struct Slot<T> {
+    generation: u64,
+    entry: Entry<T>,
+}
+
+enum Entry<T> {
+    Occupied(Node<T>),
+    Vacant { next_free: Link },
+}
+
+struct Node<T> {
+    elem: T,
+    prev: Link,
+    next: Link,
+}
+
+pub struct Iter<'a, T> {
+    slots: &'a [Slot<T>],
+    front: Link,
+    back: Link,
+    len: usize,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.len == 0 {
+            return None;
+        }
+
+        self.front.map(|index| {
+            let node = self.slots[index].node();
+            self.len -= 1;
+            self.front = node.next;
+            &node.elem
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<T> DoubleEndedIterator for Iter<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.len == 0 {
+            return None;
+        }
+
+        self.back.map(|index| {
+            let node = self.slots[index].node();
+            self.len -= 1;
+            self.back = node.prev;
+            &node.elem
+        })
+    }
+}
+
+impl<T> ExactSizeIterator for Iter<'_, T> {}
+
+impl<T> Slot<T> {
+    fn node(&self) -> &Node<T> {
+        match &self.entry {
+            Entry::Occupied(node) => node,
+            Entry::Vacant { .. } => unreachable!("linked slot is vacant"),
+        }
+    }
+
+    fn node_mut(&mut self) -> &mut Node<T> {
+        match &mut self.entry {
+            Entry::Occupied(node) => node,
+            Entry::Vacant { .. } => unreachable!("linked slot is vacant"),
+        }
+    }
+}
+
+impl<T> Default for List<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> List<T> {
+    pub fn new() -> Self {
+        List {
+            slots: Vec::new(),
+            head: None,
+            tail: None,
+            free: None,
+            len: 0,
+        }
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        List {
+            slots: Vec::with_capacity(capacity),
+            ..List::new()
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn clear(&mut self) {
+        while self.pop_front().is_some() {}
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            slots: &self.slots,
+            front: self.head,
+            back: self.tail,
+            len: self.len,
+        }
+    }
+
+    pub fn push_front(&mut self, elem: T) -> Handle {
+        let index = self.alloc(elem);
+        self.link_front(index);
+        self.handle(index)
+    }
+
+    pub fn push_back(&mut self, elem: T) -> Handle {
+        let index = self.alloc(elem);
+        self.link_back(index);
+        self.handle(index)
+    }
+
+    pub fn pop_front(&mut self) -> Option<T> {
+        self.head.map(|index| self.release(index))
+    }
+
+    pub fn pop_back(&mut self) -> Option<T> {
+        self.tail.map(|index| self.release(index))
+    }
+
+    pub fn front(&self) -> Option<&T> {
+        self.head.map(|index| &self.slots[index].node().elem)
+    }
+
+    pub fn back(&self) -> Option<&T> {
+        self.tail.map(|index| &self.slots[index].node().elem)
+    }
+
+    pub fn front_handle(&self) -> Option<Handle> {
+        self.head.map(|index| self.handle(index))
+    }
+
+    pub fn back_handle(&self) -> Option<Handle> {
+        self.tail.map(|index| self.handle(index))
+    }
+
+    pub fn contains(&self, handle: Handle) -> bool {
+        self.resolve(handle).is_some()
+    }
+
+    pub fn get(&self, handle: Handle) -> Option<&T> {
+        self.resolve(handle)
+            .map(|index| &self.slots[index].node().elem)
+    }
+
+    pub fn get_mut(&mut self, handle: Handle) -> Option<&mut T> {
+        self.resolve(handle)
+            .map(|index| &mut self.slots[index].node_mut().elem)
+    }
+
+    pub fn remove(&mut self, handle: Handle) -> Option<T> {
+        self.resolve(handle).map(|index| self.release(index))
+    }
+
+    pub fn move_to_front(&mut self, handle: Handle) -> Option<()> {
+        let index = self.resolve(handle)?;
+        if self.head != Some(index) {
+            self.unlink(index);
+            self.link_front(index);
+        }
+        Some(())
+    }
+
+    pub fn move_to_back(&mut self, handle: Handle) -> Option<()> {
+        let index = self.resolve(handle)?;
+        if self.tail != Some(index) {
+            self.unlink(index);
+            self.link_back(index);
+        }
+        Some(())
+    }
+
+    fn handle(&self, index: usize) -> Handle {
+        Handle {
+            index,
+            generation: self.slots[index].generation,
+        }
+    }
+
+    fn resolve(&self, handle: Handle) -> Option<usize> {
+        match self.slots.get(handle.index) {
+            Some(Slot {
+                generation,
+                entry: Entry::Occupied(_),
+            }) if *generation == handle.generation => Some(handle.index),
+            _ => None,
+        }
+    }
+
+    // Puts `elem` in a free slot (or a new one) without linking it anywhere.
+    fn alloc(&mut self, elem: T) -> usize {
+        let node = Node {
+            elem,
+            prev: None,
+            next: None,
+        };
+
+        self.len += 1;
+        match self.free {
+            Some(index) => {
+                let slot = &mut self.slots[index];
+                match mem::replace(&mut slot.entry, Entry::Occupied(node)) {
+                    Entry::Vacant { next_free } => self.free = next_free,
+                    Entry::Occupied(_) => unreachable!("free slot is occupied"),
+                }
+                index
+            }
+            None => {
+                self.slots.push(Slot {
+                    generation: 0,
+                    entry: Entry::Occupied(node),
+                });
+                self.slots.len() - 1
+            }
+        }
+    }
+
+    // Unlinks the node at `index` and hands its slot back to the free list.
+    fn release(&mut self, index: usize) -> T {
+        self.unlink(index);
+        self.len -= 1;
+
+        let slot = &mut self.slots[index];
+        slot.generation += 1;
+        let entry = mem::replace(
+            &mut slot.entry,
+            Entry::Vacant {
+                next_free: self.free,
+            },
+        );
+        self.free = Some(index);
+
+        match entry {
+            Entry::Occupied(node) => node.elem,
+            Entry::Vacant { .. } => unreachable!("linked slot is vacant"),
+        }
+    }
+
+    fn link_front(&mut self, index: usize) {
+        let old_head = self.head;
+        {
+            let node = self.slots[index].node_mut();
+            node.prev = None;
+            node.next = old_head;
+        }
+
+        match old_head {
+            Some(old) => self.slots[old].node_mut().prev = Some(index),
+            None => self.tail = Some(index),
+        }
+        self.head = Some(index);
+    }
+
+    fn link_back(&mut self, index: usize) {
+        let old_tail = self.tail;
+        {
+            let node = self.slots[index].node_mut();
+            node.next = None;
+            node.prev = old_tail;
+        }
+
+        match old_tail {
+            Some(old) => self.slots[old].node_mut().next = Some(index),
+            None => self.head = Some(index),
+        }
+        self.tail = Some(index);
+    }
+
+    fn unlink(&mut self, index: usize) {
+        let (prev, next) = {
+            let node = self.slots[index].node_mut();
+            (node.prev.take(), node.next.take())
+        };
+
+        match prev {
+            Some(prev) => self.slots[prev].node_mut().next = next,
+            None => self.head = next,
+        }
+        match next {
+            Some(next) => self.slots[next].node_mut().prev = prev,
+            None => self.tail = prev,
+        }
+    }
+}
+
+impl<'a, T> IntoIterator for &'a List<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<T> Collection<T> for List<T> {
+    type Ref<'a>
+        = &'a T
+    where
+        Self: 'a;
+
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn clear(&mut self) {
+        List::clear(self)
+    }
+}
+
+impl<T> Stack<T> for List<T> {
+    fn push(&mut self, elem: T) {
+        self.push_front(elem);
+    }
+
+    fn pop(&mut self) -> Option<T> {
+        self.pop_front()
+    }
+
+    fn peek(&self) -> Option<&T> {
+        self.front()
+    }
+}
+
+impl<T> Queue<T> for List<T> {
+    fn push(&mut self, elem: T) {
+        self.push_back(elem);
+    }
+
+    fn pop(&mut self) -> Option<T> {
+        self.pop_front()
+    }
+
+    fn peek(&self) -> Option<&T> {
+        self.front()
+    }
+}
+
+impl<T> Deque<T> for List<T> {
+    fn push_front(&mut self, elem: T) {
+        List::push_front(self, elem);
+    }
+
+    fn push_back(&mut self, elem: T) {
+        List::push_back(self, elem);
+    }
+
+    fn pop_front(&mut self) -> Option<T> {
+        List::pop_front(self)
+    }
+
+    fn pop_back(&mut self) -> Option<T> {
+        List::pop_back(self)
+    }
+
+    fn peek_front(&self) -> Option<&T> {
+        self.front()
+    }
+
+    fn peek_back(&self) -> Option<&T> {
+        self.back()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::List;
+
+    fn to_vec<T: Clone>(list: &List<T>) -> Vec<T> {
+        let forward: Vec<T> = list.iter().cloned().collect();
+        let mut backward: Vec<T> = list.iter().rev().cloned().collect();
+        backward.reverse();
+        assert_eq!(forward.len(), backward.len());
+        assert_eq!(forward.len(), list.len());
+        forward
+    }
+
+    #[test]
+    fn basics() {
+        let mut list = List::new();
+        assert_eq!(list.pop_front(), None);
+        assert_eq!(list.pop_back(), None);
+
+        list.push_back(2);
+        list.push_front(1);
+        list.push_back(3);
+        assert_eq!(to_vec(&list), vec![1, 2, 3]);
+        assert_eq!(list.front(), Some(&1));
+        assert_eq!(list.back(), Some(&3));
+
+        assert_eq!(list.pop_front(), Some(1));
+        assert_eq!(list.pop_back(), Some(3));
+        assert_eq!(list.pop_back(), Some(2));
+        assert_eq!(list.pop_front(), None);
+        assert!(list.is_empty());
+    }
+
+    #[test]
+    fn handles() {
+        let mut list = List::new();
+        let a = list.push_back('a');
+        let b = list.push_back('b');
+        let c = list.push_back('c');
+
+        assert_eq!(list.get(b), Some(&'b'));
+        *list.get_mut(c).unwrap() = 'C';
+        assert_eq!(list.front_handle(), Some(a));
+        assert_eq!(list.back_handle(), Some(c));
+
+        assert_eq!(list.remove(b), Some('b'));
+        assert_eq!(to_vec(&list), vec!['a', 'C']);
+
+        list.move_to_front(c).unwrap();
+        assert_eq!(to_vec(&list), vec!['C', 'a']);
+        list.move_to_back(c).unwrap();
+        assert_eq!(to_vec(&list), vec!['a', 'C']);
+        list.move_to_back(c).unwrap();
+        assert_eq!(to_vec(&list), vec!['a', 'C']);
+    }
+
+    #[test]
+    fn stale_handles() {
+        let mut list = List::new();
+        let a = list.push_back(1);
+        let b = list.push_back(2);
+
+        assert_eq!(list.remove(a), Some(1));
+        assert!(!list.contains(a));
+        assert_eq!(list.remove(a), None);
+        assert_eq!(list.get(a), None);
+        assert_eq!(list.get_mut(a), None);
+        assert_eq!(list.move_to_front(a), None);
+        assert_eq!(list.move_to_back(a), None);
+
+        // The freed slot gets reused, but the old handle still doesn't match
+        let c = list.push_front(3);
+        assert_ne!(a, c);
+        assert_eq!(list.get(a), None);
+        assert_eq!(list.get(c), Some(&3));
+        assert_eq!(list.remove(a), None);
+        assert_eq!(to_vec(&list), vec![3, 2]);
+
+        list.pop_back();
+        assert_eq!(list.get(b), None);
+        assert_eq!(list.move_to_front(b), None);
+        assert_eq!(to_vec(&list), vec![3]);
+    }
+
+    #[test]
+    fn slots_are_reused() {
+        let mut list = List::with_capacity(4);
+        let handles: Vec<_> = (0..4).map(|i| list.push_back(i)).collect();
+
+        for round in 0..100 {
+            let handle = list.front_handle().unwrap();
+            let elem = list.remove(handle).unwrap();
+            list.push_back(elem + 4);
+            assert_eq!(list.slots.len(), 4);
+            assert_eq!(list.front(), Some(&(round + 1)));
+        }
+
+        assert!(handles.iter().all(|&handle| !list.contains(handle)));
+        assert_eq!(to_vec(&list), vec![100, 101, 102, 103]);
+    }
+
+    #[test]
+    fn iter() {
+        let mut list = List::new();
+        for i in 0..5 {
+            list.push_back(i);
+        }
+
+        let mut iter = list.iter();
+        assert_eq!(iter.len(), 5);
+        assert_eq!(iter.next(), Some(&0));
+        assert_eq!(iter.next_back(), Some(&4));
+        assert_eq!(iter.next(), Some(&1));
+        assert_eq!(iter.next_back(), Some(&3));
+        assert_eq!(iter.next(), Some(&2));
+        assert_eq!(iter.next(), None);
+        assert_eq!(iter.next_back(), None);
+
+        let sum: i32 = (&list).into_iter().sum();
+        assert_eq!(sum, 10);
+    }
+}
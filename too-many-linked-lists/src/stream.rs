@@ -0,0 +1,611 @@
+use alloc::boxed::Box;
+use alloc::rc::Rc;
+use core::cell::{Cell, OnceCell, RefCell};
+use core::fmt;
+
+// A persistent list whose tails are computed on demand. Heads are always
+// evaluated; each tail is a thunk that runs at most once and is then shared
+// by every clone of the stream.
+//
+// Forcing a tail only ever forces the tails it directly depends on, so
+// walking far down a stream never recurses. The combinators build new
+// streams out of `Rc`-shared closures, and so need `'static` elements.
+pub struct Stream<T> {
+    head: Link<T>,
+}
+
+type Link<T> = Option<Rc<Node<T>>>;
+
+type Thunk<T> = Box<dyn Suspended<T>>;
+
+// A tail's closure. Closures have to be `'static`, so whatever one captures
+// can be dropped apart from the node it belongs to.
+trait Suspended<T> {
+    fn run(self: Box<Self>) -> Stream<T>;
+
+    #[cfg(any(feature = "std", test))]
+    fn erase(self: Box<Self>) -> Box<dyn Erased>;
+}
+
+impl<T, F> Suspended<T> for F
+where
+    F: FnOnce() -> Stream<T> + 'static,
+{
+    fn run(self: Box<Self>) -> Stream<T> {
+        self()
+    }
+
+    #[cfg(any(feature = "std", test))]
+    fn erase(self: Box<Self>) -> Box<dyn Erased> {
+        self
+    }
+}
+
+struct Node<T> {
+    elem: T,
+    tail: OnceCell<Stream<T>>,
+    thunk: Cell<Option<Thunk<T>>>,
+}
+
+impl<T> Node<T> {
+    fn force(&self) -> &Stream<T> {
+        self.tail.get_or_init(|| {
+            let thunk = self
+                .thunk
+                .take()
+                .expect("stream tail forced while being evaluated");
+            thunk.run()
+        })
+    }
+}
+
+pub struct Iter<'a, T> {
+    next: Option<&'a Node<T>>,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next.map(|node| {
+            self.next = node.force().head.as_deref();
+            &node.elem
+        })
+    }
+}
+
+// Unlike `Iter`, this lets go of each node as it moves on, so walking an
+// unshared stream runs in constant memory.
+pub struct IntoIter<T>(Stream<T>);
+
+impl<T: Clone> Iterator for IntoIter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let elem = self.0.head()?.clone();
+        self.0 = self.0.tail();
+        Some(elem)
+    }
+}
+
+impl<T> Stream<T> {
+    pub fn new() -> Self {
+        Stream { head: None }
+    }
+
+    pub fn cons<F>(elem: T, tail: F) -> Self
+    where
+        F: FnOnce() -> Stream<T> + 'static,
+    {
+        Stream {
+            head: Some(Rc::new(Node {
+                elem,
+                tail: OnceCell::new(),
+                thunk: Cell::new(Some(Box::new(tail))),
+            })),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.head.is_none()
+    }
+
+    pub fn head(&self) -> Option<&T> {
+        self.head.as_ref().map(|node| &node.elem)
+    }
+
+    // Forces the tail if this is its first use.
+    pub fn tail(&self) -> Stream<T> {
+        match &self.head {
+            Some(node) => node.force().clone(),
+            None => Stream::new(),
+        }
+    }
+
+    // Whether the tail has been evaluated yet, without forcing it.
+    pub fn is_forced(&self) -> bool {
+        self.head
+            .as_ref()
+            .is_none_or(|node| node.tail.get().is_some())
+    }
+
+    // Everything `iter` walks past stays memoized for as long as `self` is
+    // alive. Use `into_iter` on a clone to stream without keeping it.
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            next: self.head.as_deref(),
+        }
+    }
+}
+
+impl<T: Clone + 'static> Stream<T> {
+    // `seed`, `f(seed)`, `f(f(seed))`, ...
+    pub fn iterate<F>(seed: T, f: F) -> Self
+    where
+        F: Fn(&T) -> T + 'static,
+    {
+        iterate(seed, Rc::new(f))
+    }
+
+    // Calls `f` for the head straight away and for every later element as
+    // its tail is forced. The stream ends at the first `None`.
+    pub fn from_fn<F>(f: F) -> Self
+    where
+        F: FnMut() -> Option<T> + 'static,
+    {
+        from_fn(Rc::new(RefCell::new(f)))
+    }
+
+    pub fn take(&self, n: usize) -> Stream<T> {
+        match self.head() {
+            Some(elem) if n > 0 => {
+                let rest = self.clone();
+                // The last element mustn't force anything past itself
+                Stream::cons(elem.clone(), move || match n {
+                    1 => Stream::new(),
+                    _ => rest.tail().take(n - 1),
+                })
+            }
+            _ => Stream::new(),
+        }
+    }
+
+    pub fn map<U, F>(&self, f: F) -> Stream<U>
+    where
+        U: 'static,
+        F: Fn(&T) -> U + 'static,
+    {
+        map(self, Rc::new(f))
+    }
+
+    // Evaluates up to the first element that passes `pred`, so filtering an
+    // infinite stream down to nothing never returns.
+    pub fn filter<P>(&self, pred: P) -> Stream<T>
+    where
+        P: Fn(&T) -> bool + 'static,
+    {
+        filter(self, Rc::new(pred))
+    }
+
+    // As long as the shorter of the two.
+    pub fn zip<U: Clone + 'static>(&self, other: &Stream<U>) -> Stream<(T, U)> {
+        match (self.head(), other.head()) {
+            (Some(a), Some(b)) => {
+                let (left, right) = (self.clone(), other.clone());
+                Stream::cons((a.clone(), b.clone()), move || {
+                    left.tail().zip(&right.tail())
+                })
+            }
+            _ => Stream::new(),
+        }
+    }
+}
+
+fn iterate<T, F>(seed: T, f: Rc<F>) -> Stream<T>
+where
+    T: Clone + 'static,
+    F: Fn(&T) -> T + 'static,
+{
+    let prev = seed.clone();
+    Stream::cons(seed, move || iterate(f(&prev), f))
+}
+
+fn from_fn<T, F>(f: Rc<RefCell<F>>) -> Stream<T>
+where
+    T: 'static,
+    F: FnMut() -> Option<T> + 'static,
+{
+    let elem = (f.borrow_mut())();
+    match elem {
+        Some(elem) => Stream::cons(elem, move || from_fn(f)),
+        None => Stream::new(),
+    }
+}
+
+fn map<T, U, F>(stream: &Stream<T>, f: Rc<F>) -> Stream<U>
+where
+    T: 'static,
+    U: 'static,
+    F: Fn(&T) -> U + 'static,
+{
+    match stream.head() {
+        Some(elem) => {
+            let rest = stream.clone();
+            Stream::cons(f(elem), move || map(&rest.tail(), f))
+        }
+        None => Stream::new(),
+    }
+}
+
+fn filter<T, P>(stream: &Stream<T>, pred: Rc<P>) -> Stream<T>
+where
+    T: Clone + 'static,
+    P: Fn(&T) -> bool + 'static,
+{
+    let mut cur = stream.clone();
+    while let Some(elem) = cur.head() {
+        if pred(elem) {
+            let elem = elem.clone();
+            return Stream::cons(elem, move || filter(&cur.tail(), pred));
+        }
+        cur = cur.tail();
+    }
+    Stream::new()
+}
+
+impl<T> Default for Stream<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// O(1): the clone shares every node, evaluated or not, with `self`.
+impl<T> Clone for Stream<T> {
+    fn clone(&self) -> Self {
+        Stream {
+            head: self.head.clone(),
+        }
+    }
+}
+
+// Only shows what has been evaluated so far, with `..` for the rest.
+impl<T: fmt::Debug> fmt::Debug for Stream<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        struct Unforced;
+
+        impl fmt::Debug for Unforced {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("..")
+            }
+        }
+
+        let mut list = f.debug_list();
+        let mut cur = self.head.as_deref();
+        while let Some(node) = cur {
+            list.entry(&node.elem);
+            match node.tail.get() {
+                Some(tail) => cur = tail.head.as_deref(),
+                None => {
+                    list.entry(&Unforced);
+                    break;
+                }
+            }
+        }
+        list.finish()
+    }
+}
+
+impl<T: Clone> IntoIterator for Stream<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter(self)
+    }
+}
+
+impl<'a, T> IntoIterator for &'a Stream<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+// Forced tails are taken apart in a loop, like `first::List`'s nodes. An
+// unevaluated thunk can capture streams of its own that nest as deep as the
+// stream is long, so with `std` only the outermost thunk dropped on a thread
+// is dropped right away: thunks dropped meanwhile are queued and dropped after
+// it, one at a time. Only thunks are ever queued, and those are `'static`.
+impl<T> Drop for Stream<T> {
+    fn drop(&mut self) {
+        let mut cur_link = self.head.take();
+        while let Some(node) = cur_link {
+            let Ok(mut node) = Rc::try_unwrap(node) else {
+                break;
+            };
+            if let Some(thunk) = node.thunk.get_mut().take() {
+                drop_thunk(thunk);
+            }
+            cur_link = node.tail.take().and_then(|mut tail| tail.head.take());
+        }
+    }
+}
+
+#[cfg(any(feature = "std", test))]
+trait Erased {}
+
+#[cfg(any(feature = "std", test))]
+impl<T: ?Sized> Erased for T {}
+
+#[cfg(any(feature = "std", test))]
+fn drop_thunk<T>(thunk: Thunk<T>) {
+    std::thread_local! {
+        static DEFERRED: RefCell<Option<Vec<Box<dyn Erased>>>> = const { RefCell::new(None) };
+    }
+
+    // Reopens the queue even if a thunk's drop panics. Whatever is still
+    // queued then is leaked rather than dropped during unwinding.
+    struct Draining;
+
+    impl Drop for Draining {
+        fn drop(&mut self) {
+            let _ =
+                DEFERRED.try_with(|deferred| deferred.borrow_mut().take().map(core::mem::forget));
+        }
+    }
+
+    // If the queue is already gone because the thread is exiting, the thunk
+    // is just dropped in place
+    let mut thunk = Some(thunk.erase());
+    let outermost = DEFERRED
+        .try_with(|deferred| {
+            let mut deferred = deferred.borrow_mut();
+            match deferred.as_mut() {
+                Some(queue) => {
+                    queue.extend(thunk.take());
+                    false
+                }
+                None => {
+                    *deferred = Some(Vec::new());
+                    true
+                }
+            }
+        })
+        .unwrap_or(false);
+    if !outermost {
+        return;
+    }
+
+    let _draining = Draining;
+    drop(thunk);
+    while let Some(thunk) =
+        DEFERRED.with(|deferred| deferred.borrow_mut().as_mut().and_then(Vec::pop))
+    {
+        drop(thunk);
+    }
+}
+
+// Without `std` there's no thread-local queue, so a thunk's captured streams
+// are dropped in place, nesting as deep as the thunks do.
+#[cfg(not(any(feature = "std", test)))]
+fn drop_thunk<T>(thunk: Thunk<T>) {
+    drop(thunk);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Stream;
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    fn naturals() -> Stream<u64> {
+        Stream::iterate(0, |n| n + 1)
+    }
+
+    fn collect<T: Clone>(stream: &Stream<T>) -> Vec<T> {
+        stream.iter().cloned().collect()
+    }
+
+    #[test]
+    fn basics() {
+        let empty = Stream::<i32>::new();
+        assert!(empty.is_empty());
+        assert_eq!(empty.head(), None);
+        assert!(empty.tail().is_empty());
+
+        let stream = Stream::cons(1, || Stream::cons(2, Stream::new));
+        assert_eq!(stream.head(), Some(&1));
+        assert!(!stream.is_forced());
+        assert_eq!(stream.tail().head(), Some(&2));
+        assert!(stream.is_forced());
+        assert!(stream.tail().tail().is_empty());
+        assert_eq!(collect(&stream), vec![1, 2]);
+        assert_eq!(stream.clone().into_iter().collect::<Vec<_>>(), vec![1, 2]);
+    }
+
+    #[test]
+    fn tails_are_memoized() {
+        let calls = Rc::new(Cell::new(0));
+        let stream = Stream::cons(1, {
+            let calls = calls.clone();
+            move || {
+                calls.set(calls.get() + 1);
+                Stream::cons(2, Stream::new)
+            }
+        });
+        let copy = stream.clone();
+        assert_eq!(calls.get(), 0);
+
+        stream.tail();
+        stream.tail();
+        copy.tail();
+        assert_eq!(calls.get(), 1);
+        assert!(Rc::ptr_eq(
+            stream.tail().head.as_ref().unwrap(),
+            copy.tail().head.as_ref().unwrap()
+        ));
+    }
+
+    #[test]
+    fn debug_shows_evaluated_prefix() {
+        let stream = naturals();
+        assert_eq!(format!("{:?}", stream), "[0, ..]");
+        stream.iter().nth(2);
+        assert_eq!(format!("{:?}", stream), "[0, 1, 2, 3, ..]");
+        assert_eq!(
+            format!("{:?}", Stream::cons(1, Stream::new).take(1)),
+            "[1, ..]"
+        );
+        assert_eq!(format!("{:?}", Stream::<i32>::new()), "[]");
+    }
+
+    #[test]
+    fn combinators_are_lazy() {
+        let evaluated = Rc::new(Cell::new(0));
+        let stream = Stream::from_fn({
+            let evaluated = evaluated.clone();
+            move || {
+                evaluated.set(evaluated.get() + 1);
+                Some(evaluated.get())
+            }
+        });
+        assert_eq!(evaluated.get(), 1);
+
+        let doubled = stream.map(|n| n * 2);
+        let evens = doubled.filter(|n| n % 4 == 0);
+        let pairs = doubled.zip(&evens).take(3);
+        // Filtering had to look one element ahead
+        assert_eq!(evaluated.get(), 2);
+
+        assert_eq!(collect(&pairs), vec![(2, 4), (4, 8), (6, 12)]);
+        assert_eq!(evaluated.get(), 6);
+    }
+
+    #[test]
+    fn combinators() {
+        assert_eq!(collect(&naturals().take(5)), vec![0, 1, 2, 3, 4]);
+        assert!(naturals().take(0).is_empty());
+        assert_eq!(
+            collect(&naturals().map(|n| n * n).take(4)),
+            vec![0, 1, 4, 9]
+        );
+        assert_eq!(
+            collect(&naturals().filter(|n| n % 3 == 0).take(4)),
+            vec![0, 3, 6, 9]
+        );
+        assert_eq!(
+            collect(&naturals().zip(&naturals().map(|n| n * 10)).take(3)),
+            vec![(0, 0), (1, 10), (2, 20)]
+        );
+        assert_eq!(
+            collect(&Stream::iterate(1, |n| n * 2).take(5)),
+            vec![1, 2, 4, 8, 16]
+        );
+
+        // Finite streams end wherever the shortest input does
+        let short = naturals().take(2);
+        assert_eq!(collect(&naturals().zip(&short)), vec![(0, 0), (1, 1)]);
+        assert_eq!(collect(&short.take(10)), vec![0, 1]);
+        assert!(short.filter(|&n| n > 5).is_empty());
+
+        let mut countdown = 3;
+        let stream = Stream::from_fn(move || {
+            countdown -= 1;
+            (countdown >= 0).then_some(countdown)
+        });
+        assert_eq!(collect(&stream), vec![2, 1, 0]);
+    }
+
+    fn sieve(stream: Stream<u64>) -> Stream<u64> {
+        let p = *stream.head().unwrap();
+        Stream::cons(p, move || sieve(stream.tail().filter(move |n| n % p != 0)))
+    }
+
+    #[test]
+    fn primes() {
+        let primes = sieve(naturals().filter(|&n| n >= 2));
+        assert_eq!(
+            collect(&primes.take(10)),
+            vec![2, 3, 5, 7, 11, 13, 17, 19, 23, 29]
+        );
+
+        let fibs = Stream::iterate((0u64, 1u64), |&(a, b)| (b, a + b)).map(|&(a, _)| a);
+        assert_eq!(fibs.iter().nth(50), Some(&12_586_269_025));
+    }
+
+    #[test]
+    fn elements_dropping_borrowed_streams() {
+        struct Guard<'a>(&'a Cell<usize>);
+
+        impl Drop for Guard<'_> {
+            fn drop(&mut self) {
+                self.0.set(self.0.get() + 1);
+            }
+        }
+
+        // Drops a stream of borrowed elements while the stream it's in is
+        // being dropped; its elements mustn't outlive `local`
+        struct Outer(Rc<Cell<usize>>);
+
+        impl Drop for Outer {
+            fn drop(&mut self) {
+                let local = Cell::new(0);
+                // Unlike the closure, `Stream::new` for borrowed elements isn't `'static`
+                #[allow(clippy::redundant_closure)]
+                drop(Stream::cons(Guard(&local), || Stream::new()));
+                assert_eq!(local.get(), 1);
+                self.0.set(self.0.get() + 1);
+            }
+        }
+
+        let drops = Rc::new(Cell::new(0));
+        let mut stream = Stream::new();
+        for _ in 0..100 {
+            let tail = stream;
+            stream = Stream::cons(Outer(drops.clone()), move || tail);
+        }
+        assert!(stream.iter().nth(50).is_some());
+        drop(stream);
+        assert_eq!(drops.get(), 100);
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn long_streams() {
+        // Streaming lets go of every node once it's been passed
+        let doubled = naturals().map(|n| n * 2);
+        assert_eq!(doubled.into_iter().nth(10_000_000), Some(20_000_000));
+        let evens = naturals().filter(|n| n % 2 == 0);
+        assert_eq!(evens.into_iter().nth(1_000_000), Some(2_000_000));
+
+        // Memoizing keeps everything alive until the head is dropped
+        let stream = naturals().zip(&naturals());
+        assert_eq!(stream.iter().nth(1_000_000), Some(&(1_000_000, 1_000_000)));
+        drop(stream);
+
+        // Thunks that never ran can nest captured streams just as deep
+        let mut stream = Stream::new();
+        for n in 0..1_000_000 {
+            let tail = stream;
+            stream = Stream::cons(n, move || tail);
+        }
+        drop(stream);
+
+        let mut stream = naturals();
+        for _ in 0..1_000_000 {
+            stream = stream.map(|n| n + 1);
+        }
+        assert_eq!(stream.head(), Some(&1_000_000));
+        drop(stream);
+
+        // And a partly forced one mixes both kinds of link
+        let mut stream = Stream::new();
+        for n in 0..1_000_000 {
+            let tail = stream;
+            stream = Stream::cons(n, move || tail);
+        }
+        assert_eq!(stream.iter().nth(500_000), Some(&499_999));
+    }
+}
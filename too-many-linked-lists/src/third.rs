@@ -1,4 +1,16 @@
-use std::rc::Rc;
+use alloc::rc::Rc;
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+use core::fmt;
+use core::hash::{Hash, Hasher};
+use core::ptr;
+
+use crate::traits::{Collection, Stack};
+use crate::viz::{self, Graph, ToDot};
+
+pub mod queue;
+pub mod sync;
+pub mod zipper;
 
 type Link<T> = Option<Rc<Node<T>>>;
 
@@ -16,7 +28,6 @@ impl<'a, T> Iterator for Iter<'a, T> {
     }
 }
 
-#[derive(Debug)]
 pub struct List<T> {
     head: Link<T>,
 }
@@ -47,6 +58,220 @@ impl<T> List<T> {
             head: self.head.as_ref().and_then(|node| node.next.clone()),
         }
     }
+
+    // Whether both lists are made of the very same nodes, not just equal
+    // elements.
+    pub fn ptr_eq(&self, other: &List<T>) -> bool {
+        match (&self.head, &other.head) {
+            (Some(a), Some(b)) => Rc::ptr_eq(a, b),
+            (None, None) => true,
+            _ => false,
+        }
+    }
+
+    // The first node's address, or null for the empty list. It stays the same
+    // for as long as the node is alive, so it can key a map of nodes.
+    pub(crate) fn head_ptr(&self) -> *const () {
+        self.head
+            .as_ref()
+            .map_or(ptr::null(), |node| Rc::as_ptr(node) as *const ())
+    }
+
+    pub fn len(&self) -> usize {
+        self.iter().count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.head.is_none()
+    }
+
+    pub fn nth(&self, n: usize) -> Option<&T> {
+        self.iter().nth(n)
+    }
+
+    pub fn contains(&self, x: &T) -> bool
+    where
+        T: PartialEq,
+    {
+        self.iter().any(|elem| elem == x)
+    }
+
+    pub fn fold<B, F>(&self, init: B, f: F) -> B
+    where
+        F: FnMut(B, &T) -> B,
+    {
+        self.iter().fold(init, f)
+    }
+
+    // Shares every node of `other`, only `self` gets copied.
+    pub fn append(&self, other: &List<T>) -> List<T>
+    where
+        T: Clone,
+    {
+        if other.is_empty() {
+            return self.clone();
+        }
+
+        List {
+            head: link_from(self.iter().cloned(), other.head.clone()),
+        }
+    }
+
+    pub fn reverse(&self) -> List<T>
+    where
+        T: Clone,
+    {
+        self.fold(List::new(), |acc, elem| acc.prepend(elem.clone()))
+    }
+
+    pub fn map<U, F>(&self, f: F) -> List<U>
+    where
+        F: FnMut(&T) -> U,
+    {
+        List {
+            head: link_from(self.iter().map(f), None),
+        }
+    }
+
+    // Shares the longest suffix in which every element passes `pred`.
+    pub fn filter<P>(&self, mut pred: P) -> List<T>
+    where
+        T: Clone,
+        P: FnMut(&T) -> bool,
+    {
+        let mut kept = Vec::new();
+        let mut pending = Vec::new();
+        let mut shared = &self.head;
+
+        let mut cur_link = &self.head;
+        while let Some(node) = cur_link {
+            if pred(&node.elem) {
+                pending.push(&node.elem);
+            } else {
+                kept.append(&mut pending);
+                shared = &node.next;
+            }
+            cur_link = &node.next;
+        }
+
+        List {
+            head: link_from(kept.into_iter().cloned(), shared.clone()),
+        }
+    }
+
+    pub fn take(&self, n: usize) -> List<T>
+    where
+        T: Clone,
+    {
+        if self.drop(n).is_empty() {
+            return self.clone();
+        }
+
+        List {
+            head: link_from(self.iter().take(n).cloned(), None),
+        }
+    }
+
+    pub fn drop(&self, n: usize) -> List<T> {
+        let mut cur_link = &self.head;
+        for _ in 0..n {
+            match cur_link {
+                Some(node) => cur_link = &node.next,
+                None => break,
+            }
+        }
+
+        List {
+            head: cur_link.clone(),
+        }
+    }
+}
+
+// Builds a chain holding `elems` in order, ending in the (possibly shared)
+// `tail`.
+fn link_from<T>(elems: impl IntoIterator<Item = T>, tail: Link<T>) -> Link<T> {
+    let elems: Vec<T> = elems.into_iter().collect();
+    elems
+        .into_iter()
+        .rev()
+        .fold(tail, |next, elem| Some(Rc::new(Node::new(elem, next))))
+}
+
+impl<T> Default for List<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// O(1): the clone shares every node with `self`.
+impl<T> Clone for List<T> {
+    fn clone(&self) -> Self {
+        List {
+            head: self.head.clone(),
+        }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for List<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T> FromIterator<T> for List<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        List {
+            head: link_from(iter, None),
+        }
+    }
+}
+
+// Copies the spine of `self` and shares the newly collected tail, just like
+// `append`.
+impl<T: Clone> Extend<T> for List<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        *self = self.append(&iter.into_iter().collect());
+    }
+}
+
+// There's no `&mut List` counterpart: nodes may be shared with other lists,
+// so handing out `&mut T` into them isn't possible.
+impl<'a, T> IntoIterator for &'a List<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<T: PartialEq> PartialEq for List<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.iter().eq(other.iter())
+    }
+}
+
+impl<T: Eq> Eq for List<T> {}
+
+impl<T: PartialOrd> PartialOrd for List<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.iter().partial_cmp(other.iter())
+    }
+}
+
+impl<T: Ord> Ord for List<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.iter().cmp(other.iter())
+    }
+}
+
+impl<T: Hash> Hash for List<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.len().hash(state);
+        for elem in self.iter() {
+            elem.hash(state);
+        }
+    }
 }
 
 impl<T> Drop for List<T> {
@@ -62,7 +287,6 @@ impl<T> Drop for List<T> {
     }
 }
 
-#[derive(Debug)]
 struct Node<T> {
     elem: T,
     next: Link<T>,
@@ -74,9 +298,85 @@ impl<T> Node<T> {
     }
 }
 
+impl<T> Collection<T> for List<T> {
+    type Ref<'a>
+        = &'a T
+    where
+        Self: 'a;
+
+    fn len(&self) -> usize {
+        List::len(self)
+    }
+
+    fn clear(&mut self) {
+        *self = List::new();
+    }
+}
+
+// Popping only clones the element when the head node is shared with another
+// version of the list.
+impl<T: Clone> Stack<T> for List<T> {
+    fn push(&mut self, elem: T) {
+        *self = self.prepend(elem);
+    }
+
+    fn pop(&mut self) -> Option<T> {
+        self.head.take().map(|node| match Rc::try_unwrap(node) {
+            Ok(mut node) => {
+                self.head = node.next.take();
+                node.elem
+            }
+            Err(node) => {
+                self.head = node.next.clone();
+                node.elem.clone()
+            }
+        })
+    }
+
+    fn peek(&self) -> Option<&T> {
+        self.head()
+    }
+}
+
+// Shared tails are drawn once: the walk stops at the first node that some
+// earlier list already put in the graph.
+impl<T: fmt::Debug> ToDot for List<T> {
+    fn write_dot(&self, name: &str, graph: &mut Graph) {
+        let mut from = graph.root(name);
+        let mut attrs = "label=\"head\"";
+        let mut cur = self.head.as_ref();
+        while let Some(node) = cur {
+            let label = viz::rc_label(&node.elem, Rc::strong_count(node), Rc::weak_count(node));
+            let is_new = graph.node(Rc::as_ptr(node), &label);
+            graph.edge(&from, &viz::id(Rc::as_ptr(node)), attrs);
+            if !is_new {
+                break;
+            }
+            from = viz::id(Rc::as_ptr(node));
+            attrs = "";
+            cur = node.next.as_ref();
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::List;
+    use crate::test_util::hash_of;
+    use std::rc::Rc;
+
+    // True when both lists start at the very same node (or are both empty).
+    fn shares<T>(a: &List<T>, b: &List<T>) -> bool {
+        match (&a.head, &b.head) {
+            (Some(a), Some(b)) => Rc::ptr_eq(a, b),
+            (None, None) => true,
+            _ => false,
+        }
+    }
+
+    fn to_vec<T: Clone>(list: &List<T>) -> Vec<T> {
+        list.iter().cloned().collect()
+    }
 
     #[test]
     fn prepend() {
@@ -106,4 +406,143 @@ mod tests {
         assert_eq!(iter.next(), Some(&2));
         assert_eq!(iter.next(), Some(&1));
     }
+
+    #[test]
+    fn stack_pop_shared() {
+        use crate::traits::Stack;
+
+        let list = List::new().prepend(1).prepend(2);
+        let mut other = list.tail().prepend(5);
+
+        assert_eq!(other.pop(), Some(5));
+        assert_eq!(other.pop(), Some(1));
+        assert_eq!(other.pop(), None);
+
+        let mut iter = list.iter();
+        assert_eq!(iter.next(), Some(&2));
+        assert_eq!(iter.next(), Some(&1));
+        assert_eq!(iter.next(), None);
+    }
+
+    #[test]
+    fn from_iter_len_nth_contains() {
+        let list: List<i32> = (1..=4).collect();
+        assert_eq!(to_vec(&list), vec![1, 2, 3, 4]);
+        assert_eq!(list.len(), 4);
+        assert!(!list.is_empty());
+        assert_eq!(list.nth(0), Some(&1));
+        assert_eq!(list.nth(3), Some(&4));
+        assert_eq!(list.nth(4), None);
+        assert!(list.contains(&3));
+        assert!(!list.contains(&5));
+
+        let empty: List<i32> = List::new();
+        assert_eq!(empty.len(), 0);
+        assert!(empty.is_empty());
+    }
+
+    #[test]
+    fn append() {
+        let front: List<i32> = (1..=3).collect();
+        let back: List<i32> = (4..=5).collect();
+
+        let list = front.append(&back);
+        assert_eq!(to_vec(&list), vec![1, 2, 3, 4, 5]);
+        assert!(shares(&list.drop(3), &back));
+        assert!(!shares(&list, &front));
+
+        // Appending to or from an empty list shares the other side whole
+        assert!(shares(&List::new().append(&back), &back));
+        assert!(shares(&front.append(&List::new()), &front));
+    }
+
+    #[test]
+    fn reverse_map_fold() {
+        let list: List<i32> = (1..=4).collect();
+
+        assert_eq!(to_vec(&list.reverse()), vec![4, 3, 2, 1]);
+        assert_eq!(to_vec(&list.map(|elem| elem * 10)), vec![10, 20, 30, 40]);
+        assert_eq!(list.fold(0, |acc, elem| acc + elem), 10);
+        assert_eq!(
+            list.fold(String::new(), |acc, elem| format!("{}{}", acc, elem)),
+            "1234"
+        );
+
+        assert!(List::<i32>::new().reverse().is_empty());
+        assert_eq!(to_vec(&list), vec![1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn filter() {
+        let list: List<i32> = vec![1, 2, 3, 4, 6, 8].into_iter().collect();
+
+        let even = list.filter(|elem| elem % 2 == 0);
+        assert_eq!(to_vec(&even), vec![2, 4, 6, 8]);
+        // [4, 6, 8] passes as a whole, so it's the original suffix
+        assert!(shares(&even.drop(1), &list.drop(3)));
+        assert!(!shares(&even, &list.drop(1)));
+
+        let all = list.filter(|_| true);
+        assert!(shares(&all, &list));
+
+        let none = list.filter(|_| false);
+        assert!(none.is_empty());
+    }
+
+    #[test]
+    fn take_drop() {
+        let list: List<i32> = (1..=5).collect();
+
+        let dropped = list.drop(2);
+        assert_eq!(to_vec(&dropped), vec![3, 4, 5]);
+        assert!(shares(&dropped, &list.tail().tail()));
+        assert!(list.drop(10).is_empty());
+        assert!(shares(&list.drop(0), &list));
+
+        let taken = list.take(2);
+        assert_eq!(to_vec(&taken), vec![1, 2]);
+        assert!(!shares(&taken, &list));
+        assert!(list.take(0).is_empty());
+        assert!(shares(&list.take(5), &list));
+        assert!(shares(&list.take(10), &list));
+    }
+
+    #[test]
+    fn eq_ord_hash() {
+        let a: List<i32> = (1..=3).collect();
+        let b = List::new().prepend(3).prepend(2).prepend(1);
+        let c: List<i32> = (1..=4).collect();
+
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+        assert_eq!(hash_of(&a), hash_of(&b));
+        assert!(a < c);
+        assert!(c.tail() > a);
+        assert_eq!(a.cmp(&b), std::cmp::Ordering::Equal);
+    }
+
+    #[test]
+    fn clone_extend_debug() {
+        let mut list: List<i32> = (1..=3).collect();
+        let copy = list.clone();
+        assert!(shares(&copy, &list));
+        assert_eq!(List::<i32>::default(), List::new());
+
+        list.extend(vec![4, 5]);
+        assert_eq!(format!("{:?}", list), "[1, 2, 3, 4, 5]");
+        assert_eq!(format!("{:?}", copy), "[1, 2, 3]");
+
+        let mut sum = 0;
+        for elem in &list {
+            sum += elem;
+        }
+        assert_eq!(sum, 15);
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn deep_debug_and_drop() {
+        let list: List<u8> = std::iter::repeat_n(0, 1_000_000).collect();
+        assert_eq!(format!("{:?}", list).len(), 3_000_000);
+    }
 }
@@ -0,0 +1,259 @@
+use alloc::vec::Vec;
+
+use super::List;
+use crate::traits::{Collection, Queue};
+
+// Okasaki's banker's queue: `front` holds the oldest elements in order and
+// `back` holds the newest ones reversed. Whenever `back` outgrows `front` the
+// two are rotated into a new `front`, which keeps `push_back` and `pop_front`
+// amortized O(1). As with any strict (non-lazy) version of this queue, the
+// bound is only amortized along a single line of versions; repeatedly
+// operating on an old version right before a rotation pays for it every time.
+#[derive(Debug)]
+pub struct PersistentQueue<T> {
+    front: List<T>,
+    front_len: usize,
+    back: List<T>,
+    back_len: usize,
+}
+
+pub struct Iter<'a, T> {
+    front: super::Iter<'a, T>,
+    back: core::iter::Rev<alloc::vec::IntoIter<&'a T>>,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.front.next().or_else(|| self.back.next())
+    }
+}
+
+impl<T> Default for PersistentQueue<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Clone for PersistentQueue<T> {
+    fn clone(&self) -> Self {
+        PersistentQueue {
+            front: self.front.clone(),
+            front_len: self.front_len,
+            back: self.back.clone(),
+            back_len: self.back_len,
+        }
+    }
+}
+
+impl<T> PersistentQueue<T> {
+    pub fn new() -> Self {
+        PersistentQueue {
+            front: List::new(),
+            front_len: 0,
+            back: List::new(),
+            back_len: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.front_len + self.back_len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn peek(&self) -> Option<&T> {
+        self.front.head()
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            front: self.front.iter(),
+            back: self.back.iter().collect::<Vec<_>>().into_iter().rev(),
+        }
+    }
+}
+
+impl<T: Clone> PersistentQueue<T> {
+    pub fn push_back(&self, elem: T) -> PersistentQueue<T> {
+        Self::check(
+            self.front.clone(),
+            self.front_len,
+            self.back.prepend(elem),
+            self.back_len + 1,
+        )
+    }
+
+    pub fn pop_front(&self) -> Option<(&T, PersistentQueue<T>)> {
+        self.front.head().map(|elem| {
+            let rest = Self::check(
+                self.front.tail(),
+                self.front_len - 1,
+                self.back.clone(),
+                self.back_len,
+            );
+            (elem, rest)
+        })
+    }
+
+    // Restores the `back_len <= front_len` invariant, which also guarantees
+    // that `front` is only empty when the whole queue is.
+    fn check(front: List<T>, front_len: usize, back: List<T>, back_len: usize) -> Self {
+        if back_len <= front_len {
+            PersistentQueue {
+                front,
+                front_len,
+                back,
+                back_len,
+            }
+        } else {
+            PersistentQueue {
+                front: front.append(&back.reverse()),
+                front_len: front_len + back_len,
+                back: List::new(),
+                back_len: 0,
+            }
+        }
+    }
+}
+
+impl<T: Clone> FromIterator<T> for PersistentQueue<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        iter.into_iter()
+            .fold(PersistentQueue::new(), |queue, elem| queue.push_back(elem))
+    }
+}
+
+impl<T> Collection<T> for PersistentQueue<T> {
+    type Ref<'a>
+        = &'a T
+    where
+        Self: 'a;
+
+    fn len(&self) -> usize {
+        PersistentQueue::len(self)
+    }
+
+    fn clear(&mut self) {
+        *self = PersistentQueue::new();
+    }
+}
+
+impl<T: Clone> Queue<T> for PersistentQueue<T> {
+    fn push(&mut self, elem: T) {
+        *self = self.push_back(elem);
+    }
+
+    fn pop(&mut self) -> Option<T> {
+        let (elem, rest) = self.pop_front().map(|(elem, rest)| (elem.clone(), rest))?;
+        *self = rest;
+        Some(elem)
+    }
+
+    fn peek(&self) -> Option<&T> {
+        PersistentQueue::peek(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PersistentQueue;
+
+    fn to_vec<T: Clone>(queue: &PersistentQueue<T>) -> Vec<T> {
+        queue.iter().cloned().collect()
+    }
+
+    #[test]
+    fn basics() {
+        let queue = PersistentQueue::new();
+        assert!(queue.is_empty());
+        assert_eq!(queue.peek(), None);
+        assert!(queue.pop_front().is_none());
+
+        let queue = queue.push_back(1).push_back(2).push_back(3);
+        assert_eq!(queue.len(), 3);
+        assert_eq!(queue.peek(), Some(&1));
+
+        let (elem, queue) = queue.pop_front().unwrap();
+        assert_eq!(*elem, 1);
+        let queue = queue.push_back(4).push_back(5);
+
+        let mut popped = Vec::new();
+        let mut cur = queue;
+        while let Some((elem, rest)) = cur.pop_front() {
+            popped.push(*elem);
+            cur = rest;
+        }
+        assert_eq!(popped, vec![2, 3, 4, 5]);
+        assert!(cur.is_empty());
+    }
+
+    #[test]
+    fn old_versions_stay_valid() {
+        let empty = PersistentQueue::new();
+        let one = empty.push_back(1);
+        let two = one.push_back(2);
+        let three = two.push_back(3);
+        let (_, popped) = three.pop_front().unwrap();
+        let branched = two.push_back(30);
+
+        assert!(empty.is_empty());
+        assert_eq!(to_vec(&one), vec![1]);
+        assert_eq!(to_vec(&two), vec![1, 2]);
+        assert_eq!(to_vec(&three), vec![1, 2, 3]);
+        assert_eq!(to_vec(&popped), vec![2, 3]);
+        assert_eq!(to_vec(&branched), vec![1, 2, 30]);
+
+        // Old versions keep working after newer ones were derived from them
+        let (elem, rest) = two.pop_front().unwrap();
+        assert_eq!(*elem, 1);
+        assert_eq!(to_vec(&rest), vec![2]);
+        assert_eq!(to_vec(&two.push_back(20)), vec![1, 2, 20]);
+        assert_eq!(to_vec(&three), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn matches_vec_deque() {
+        use std::collections::VecDeque;
+
+        let mut versions = vec![(PersistentQueue::new(), VecDeque::new())];
+        for i in 0..200 {
+            let (queue, model) = &versions[(i * 7) % versions.len()];
+            let (queue, mut model) = (queue.clone(), model.clone());
+
+            let next = if i % 3 == 2 {
+                match queue.pop_front() {
+                    Some((elem, rest)) => {
+                        assert_eq!(Some(*elem), model.pop_front());
+                        rest
+                    }
+                    None => {
+                        assert!(model.is_empty());
+                        queue
+                    }
+                }
+            } else {
+                model.push_back(i);
+                queue.push_back(i)
+            };
+
+            assert_eq!(next.len(), model.len());
+            assert_eq!(to_vec(&next), model.iter().copied().collect::<Vec<_>>());
+            versions.push((next, model));
+        }
+
+        for (queue, model) in &versions {
+            assert_eq!(to_vec(queue), model.iter().copied().collect::<Vec<_>>());
+        }
+    }
+
+    #[test]
+    fn from_iter() {
+        let queue: PersistentQueue<i32> = (1..=5).collect();
+        assert_eq!(to_vec(&queue), vec![1, 2, 3, 4, 5]);
+        assert_eq!(queue.len(), 5);
+    }
+}
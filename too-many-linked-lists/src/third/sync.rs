@@ -0,0 +1,276 @@
+use alloc::sync::Arc;
+use core::fmt;
+
+use crate::traits::{Collection, Stack};
+use crate::viz::{self, Graph, ToDot};
+
+type Link<T> = Option<Arc<Node<T>>>;
+
+pub struct Iter<'a, T> {
+    next: Option<&'a Node<T>>,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next.map(|node| {
+            self.next = node.next.as_deref();
+            &node.elem
+        })
+    }
+}
+
+pub struct List<T> {
+    head: Link<T>,
+}
+
+impl<T> Default for List<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Clone for List<T> {
+    fn clone(&self) -> Self {
+        List {
+            head: self.head.clone(),
+        }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for List<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T> List<T> {
+    pub fn new() -> Self {
+        List { head: None }
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            next: self.head.as_deref(),
+        }
+    }
+
+    pub fn prepend(&self, elem: T) -> List<T> {
+        List {
+            head: Some(Arc::new(Node::new(elem, self.head.clone()))),
+        }
+    }
+
+    pub fn head(&self) -> Option<&T> {
+        self.head.as_ref().map(|node| &node.elem)
+    }
+
+    pub fn tail(&self) -> List<T> {
+        List {
+            head: self.head.as_ref().and_then(|node| node.next.clone()),
+        }
+    }
+}
+
+impl<T> Drop for List<T> {
+    fn drop(&mut self) {
+        let mut cur_link = self.head.take();
+        // Unlike `try_unwrap`, `into_inner` can't fail for every one of several
+        // threads letting go of the same node at once, so exactly one of
+        // them carries on down the list instead of the last `Arc` dropping
+        // the rest of it recursively.
+        while let Some(node) = cur_link {
+            match Arc::into_inner(node) {
+                Some(mut node) => cur_link = node.next.take(),
+                None => break,
+            }
+        }
+    }
+}
+
+struct Node<T> {
+    elem: T,
+    next: Link<T>,
+}
+
+impl<T> Node<T> {
+    pub fn new(elem: T, next: Link<T>) -> Self {
+        Node { elem, next }
+    }
+}
+
+impl<T> Collection<T> for List<T> {
+    type Ref<'a>
+        = &'a T
+    where
+        Self: 'a;
+
+    fn len(&self) -> usize {
+        self.iter().count()
+    }
+
+    fn clear(&mut self) {
+        *self = List::new();
+    }
+}
+
+impl<T: Clone> Stack<T> for List<T> {
+    fn push(&mut self, elem: T) {
+        *self = self.prepend(elem);
+    }
+
+    fn pop(&mut self) -> Option<T> {
+        self.head.take().map(|node| match Arc::try_unwrap(node) {
+            Ok(mut node) => {
+                self.head = node.next.take();
+                node.elem
+            }
+            Err(node) => {
+                self.head = node.next.clone();
+                let elem = node.elem.clone();
+                // If the other owners let go meanwhile, `self.head` still
+                // holds the tail, so taking it out can't recurse
+                if let Some(mut node) = Arc::into_inner(node) {
+                    node.next.take();
+                }
+                elem
+            }
+        })
+    }
+
+    fn peek(&self) -> Option<&T> {
+        self.head()
+    }
+}
+
+impl<T: fmt::Debug> ToDot for List<T> {
+    fn write_dot(&self, name: &str, graph: &mut Graph) {
+        let mut from = graph.root(name);
+        let mut attrs = "label=\"head\"";
+        let mut cur = self.head.as_ref();
+        while let Some(node) = cur {
+            let label = viz::rc_label(&node.elem, Arc::strong_count(node), Arc::weak_count(node));
+            let is_new = graph.node(Arc::as_ptr(node), &label);
+            graph.edge(&from, &viz::id(Arc::as_ptr(node)), attrs);
+            if !is_new {
+                break;
+            }
+            from = viz::id(Arc::as_ptr(node));
+            attrs = "";
+            cur = node.next.as_ref();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::List;
+    use std::thread;
+
+    #[test]
+    fn prepend() {
+        let list = List::new();
+        assert_eq!(list.head(), None);
+
+        let list1 = list.prepend(1).prepend(2).prepend(3);
+        assert_eq!(list1.head(), Some(&3));
+
+        let list2 = list1.tail();
+        assert_eq!(list2.head(), Some(&2));
+        assert_eq!(list1.head(), Some(&3));
+
+        let list = list2.tail().tail().tail();
+        assert_eq!(list.head(), None);
+    }
+
+    #[test]
+    fn iter() {
+        let list = List::new().prepend(1).prepend(2).prepend(3);
+        let mut iter = list.iter();
+        assert_eq!(iter.next(), Some(&3));
+        assert_eq!(iter.next(), Some(&2));
+        assert_eq!(iter.next(), Some(&1));
+        assert_eq!(iter.next(), None);
+    }
+
+    #[test]
+    fn clone_debug() {
+        let list = List::new().prepend(1).prepend(2);
+        let copy = list.clone();
+        assert!(std::ptr::eq(list.head().unwrap(), copy.head().unwrap()));
+        assert_eq!(format!("{:?}", copy), "[2, 1]");
+    }
+
+    #[test]
+    fn send_sync() {
+        fn is_send_sync<T: Send + Sync>() {}
+        is_send_sync::<List<i32>>();
+    }
+
+    #[test]
+    fn shared_between_threads() {
+        let base = List::new().prepend(1).prepend(2);
+
+        let handles: Vec<_> = (0..4)
+            .map(|i| {
+                let tail = base.tail();
+                thread::spawn(move || {
+                    let list = tail.prepend(i);
+                    list.iter().copied().collect::<Vec<_>>()
+                })
+            })
+            .collect();
+
+        for (i, handle) in handles.into_iter().enumerate() {
+            assert_eq!(handle.join().unwrap(), vec![i as i32, 1]);
+        }
+
+        // The snapshot the threads branched off is untouched
+        assert_eq!(base.iter().copied().collect::<Vec<_>>(), vec![2, 1]);
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn drop_long_list_on_other_thread() {
+        let mut list = List::new();
+        for i in 0..1_000_000 {
+            list = list.prepend(i);
+        }
+        let shared = list.tail().tail();
+
+        thread::spawn(move || drop(list)).join().unwrap();
+        assert_eq!(shared.head(), Some(&999_997));
+
+        thread::spawn(move || drop(shared)).join().unwrap();
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn racing_drops_of_a_shared_tail() {
+        // Both threads let go of the last two handles at the same time, so
+        // either could end up taking the shared million nodes apart
+        for _ in 0..10 {
+            let mut tail = List::new();
+            for i in 0..1_000_000 {
+                tail = tail.prepend(i);
+            }
+            let lists = [tail.prepend(-1), tail.prepend(-2)];
+            drop(tail);
+
+            let barrier = std::sync::Arc::new(std::sync::Barrier::new(2));
+            let handles: Vec<_> = lists
+                .into_iter()
+                .map(|list| {
+                    let barrier = barrier.clone();
+                    thread::spawn(move || {
+                        barrier.wait();
+                        drop(list);
+                    })
+                })
+                .collect();
+            for handle in handles {
+                handle.join().unwrap();
+            }
+        }
+    }
+}
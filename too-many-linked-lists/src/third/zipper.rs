@@ -0,0 +1,241 @@
+use super::List;
+
+// Huet's zipper: a list opened up at the focus. `left` holds the elements
+// before the focus, nearest first, and `right` the ones after it, so moving
+// or editing at the focus only touches the heads of the two contexts. Every
+// operation returns a new zipper that shares both contexts with the old one.
+//
+// A zipper always has a focus, so there's none for the empty list, and
+// deleting the last element leaves nothing to return.
+#[derive(Clone, Debug)]
+pub struct Zipper<T> {
+    left: List<T>,
+    focus: T,
+    right: List<T>,
+    index: usize,
+}
+
+impl<T: Clone> Zipper<T> {
+    // Focuses on the first element. The zipper's right context is the list's
+    // tail itself, so nothing gets copied.
+    pub fn new(list: &List<T>) -> Option<Zipper<T>> {
+        list.head().map(|elem| Zipper {
+            left: List::new(),
+            focus: elem.clone(),
+            right: list.tail(),
+            index: 0,
+        })
+    }
+
+    pub fn focus(&self) -> &T {
+        &self.focus
+    }
+
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    pub fn left(&self) -> Option<Zipper<T>> {
+        self.left.head().map(|elem| Zipper {
+            left: self.left.tail(),
+            focus: elem.clone(),
+            right: self.right.prepend(self.focus.clone()),
+            index: self.index - 1,
+        })
+    }
+
+    pub fn right(&self) -> Option<Zipper<T>> {
+        self.right.head().map(|elem| Zipper {
+            left: self.left.prepend(self.focus.clone()),
+            focus: elem.clone(),
+            right: self.right.tail(),
+            index: self.index + 1,
+        })
+    }
+
+    pub fn replace(&self, elem: T) -> Zipper<T> {
+        Zipper {
+            left: self.left.clone(),
+            focus: elem,
+            right: self.right.clone(),
+            index: self.index,
+        }
+    }
+
+    // Like `Vec::insert`: `elem` takes the focus's place and the old focus
+    // moves one to the right. The focus ends up on `elem`.
+    pub fn insert(&self, elem: T) -> Zipper<T> {
+        Zipper {
+            left: self.left.clone(),
+            focus: elem,
+            right: self.right.prepend(self.focus.clone()),
+            index: self.index,
+        }
+    }
+
+    // The focus moves to the next element, or to the previous one if there
+    // is no next one.
+    pub fn delete(&self) -> Option<Zipper<T>> {
+        if let Some(elem) = self.right.head() {
+            Some(Zipper {
+                left: self.left.clone(),
+                focus: elem.clone(),
+                right: self.right.tail(),
+                index: self.index,
+            })
+        } else {
+            self.left.head().map(|elem| Zipper {
+                left: self.left.tail(),
+                focus: elem.clone(),
+                right: List::new(),
+                index: self.index - 1,
+            })
+        }
+    }
+
+    // Copies the focus and everything before it, and shares everything after.
+    pub fn to_list(&self) -> List<T> {
+        self.left
+            .fold(self.right.prepend(self.focus.clone()), |list, elem| {
+                list.prepend(elem.clone())
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Zipper;
+    use crate::third::List;
+    use std::rc::Rc;
+
+    fn to_vec<T: Clone>(zipper: &Zipper<T>) -> Vec<T> {
+        zipper.to_list().iter().cloned().collect()
+    }
+
+    // Whether both lists continue with the very same nodes after skipping
+    // `a` and `b` elements respectively.
+    fn shares_from<T>(a: &List<T>, skip_a: usize, b: &List<T>, skip_b: usize) -> bool {
+        match (&a.drop(skip_a).head, &b.drop(skip_b).head) {
+            (Some(x), Some(y)) => Rc::ptr_eq(x, y),
+            _ => false,
+        }
+    }
+
+    #[test]
+    fn navigation() {
+        assert!(Zipper::new(&List::<i32>::new()).is_none());
+
+        let list: List<_> = (1..=3).collect();
+        let first = Zipper::new(&list).unwrap();
+        assert_eq!((first.focus(), first.index()), (&1, 0));
+        assert!(first.left().is_none());
+
+        let last = first.right().unwrap().right().unwrap();
+        assert_eq!((last.focus(), last.index()), (&3, 2));
+        assert!(last.right().is_none());
+        assert_eq!(last.left().unwrap().focus(), &2);
+
+        // Moving around doesn't change the list
+        assert_eq!(last.to_list(), list);
+        assert_eq!(first.to_list(), list);
+    }
+
+    #[test]
+    fn edits() {
+        let list: List<_> = (1..=4).collect();
+        let at_2 = Zipper::new(&list).unwrap().right().unwrap();
+
+        let replaced = at_2.replace(20);
+        assert_eq!(to_vec(&replaced), vec![1, 20, 3, 4]);
+        assert_eq!(replaced.focus(), &20);
+
+        let inserted = at_2.insert(15);
+        assert_eq!(to_vec(&inserted), vec![1, 15, 2, 3, 4]);
+        assert_eq!((inserted.focus(), inserted.index()), (&15, 1));
+        assert_eq!(inserted.right().unwrap().focus(), &2);
+
+        let deleted = at_2.delete().unwrap();
+        assert_eq!(to_vec(&deleted), vec![1, 3, 4]);
+        assert_eq!((deleted.focus(), deleted.index()), (&3, 1));
+
+        // Deleting at the end falls back to the left
+        let at_end = at_2.right().unwrap().right().unwrap().delete().unwrap();
+        assert_eq!(to_vec(&at_end), vec![1, 2, 3]);
+        assert_eq!((at_end.focus(), at_end.index()), (&3, 2));
+
+        let single = Zipper::new(&List::new().prepend(1)).unwrap();
+        assert!(single.delete().is_none());
+
+        // None of that touched the version we started from
+        assert_eq!(to_vec(&at_2), vec![1, 2, 3, 4]);
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), vec![1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn shares_structure() {
+        let list: List<_> = (0..100).collect();
+        let mut zipper = Zipper::new(&list).unwrap();
+        for _ in 0..10 {
+            zipper = zipper.right().unwrap();
+        }
+
+        // Everything after the focus is still the original list
+        let edited = zipper.replace(-1).to_list();
+        assert_eq!(edited.nth(10), Some(&-1));
+        assert!(shares_from(&edited, 11, &list, 11));
+
+        let edited = zipper.insert(-1).to_list();
+        assert!(shares_from(&edited, 12, &list, 11));
+
+        let edited = zipper.delete().unwrap().to_list();
+        assert!(shares_from(&edited, 11, &list, 12));
+
+        // Edits further right share the earlier edit's left context
+        let first = zipper.replace(-1);
+        let second = first.right().unwrap().replace(-2);
+        assert!(Rc::ptr_eq(
+            second.left.tail().head.as_ref().unwrap(),
+            first.left.head.as_ref().unwrap()
+        ));
+    }
+
+    #[test]
+    fn matches_vec() {
+        let list: List<_> = (0..10).collect();
+        let mut versions = vec![(Zipper::new(&list).unwrap(), (0..10).collect::<Vec<_>>())];
+
+        for i in 0..300 {
+            let (zipper, model) = &versions[(i * 7) % versions.len()];
+            let mut model = model.clone();
+            let at = zipper.index();
+
+            let next = match i % 5 {
+                0 => zipper.left(),
+                1 => zipper.right(),
+                2 => {
+                    model[at] = i;
+                    Some(zipper.replace(i))
+                }
+                3 => {
+                    model.insert(at, i);
+                    Some(zipper.insert(i))
+                }
+                _ if model.len() > 1 => {
+                    model.remove(at);
+                    zipper.delete()
+                }
+                _ => None,
+            };
+
+            if let Some(next) = next {
+                assert_eq!(next.focus(), &model[next.index()]);
+                assert_eq!(to_vec(&next), model);
+                versions.push((next, model));
+            }
+        }
+
+        for (zipper, model) in &versions {
+            assert_eq!(&to_vec(zipper), model);
+        }
+    }
+}
@@ -0,0 +1,209 @@
+use core::ops::Deref;
+
+// Shared by every list. `Ref` is whatever `peek` hands out: a plain `&T` for
+// most lists, but `fourth` can only give out `cell::Ref` guards.
+pub trait Collection<T> {
+    type Ref<'a>: Deref<Target = T>
+    where
+        Self: 'a;
+
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn clear(&mut self);
+}
+
+// LIFO: `pop` and `peek` see the most recently pushed element.
+pub trait Stack<T>: Collection<T> {
+    fn push(&mut self, elem: T);
+
+    fn pop(&mut self) -> Option<T>;
+
+    fn peek(&self) -> Option<Self::Ref<'_>>;
+}
+
+// FIFO: `pop` and `peek` see the least recently pushed element.
+pub trait Queue<T>: Collection<T> {
+    fn push(&mut self, elem: T);
+
+    fn pop(&mut self) -> Option<T>;
+
+    fn peek(&self) -> Option<Self::Ref<'_>>;
+}
+
+pub trait Deque<T>: Collection<T> {
+    fn push_front(&mut self, elem: T);
+
+    fn push_back(&mut self, elem: T);
+
+    fn pop_front(&mut self) -> Option<T>;
+
+    fn pop_back(&mut self) -> Option<T>;
+
+    fn peek_front(&self) -> Option<Self::Ref<'_>>;
+
+    fn peek_back(&self) -> Option<Self::Ref<'_>>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Deque, Queue, Stack};
+    use crate::{fifth, first, fourth, second, sixth, slab_list, third, unrolled};
+
+    fn peek_stack<S: Stack<i32>>(stack: &S) -> Option<i32> {
+        stack.peek().map(|elem| *elem)
+    }
+
+    fn peek_queue<Q: Queue<i32>>(queue: &Q) -> Option<i32> {
+        queue.peek().map(|elem| *elem)
+    }
+
+    fn peek_front<D: Deque<i32>>(deque: &D) -> Option<i32> {
+        deque.peek_front().map(|elem| *elem)
+    }
+
+    fn peek_back<D: Deque<i32>>(deque: &D) -> Option<i32> {
+        deque.peek_back().map(|elem| *elem)
+    }
+
+    fn check_stack<S: Stack<i32>>(mut stack: S) {
+        assert!(stack.is_empty());
+        assert_eq!(stack.len(), 0);
+        assert_eq!(stack.pop(), None);
+        assert_eq!(peek_stack(&stack), None);
+
+        stack.push(1);
+        stack.push(2);
+        stack.push(3);
+        assert!(!stack.is_empty());
+        assert_eq!(stack.len(), 3);
+        assert_eq!(peek_stack(&stack), Some(3));
+
+        assert_eq!(stack.pop(), Some(3));
+        assert_eq!(stack.pop(), Some(2));
+        stack.push(4);
+        assert_eq!(stack.len(), 2);
+        assert_eq!(peek_stack(&stack), Some(4));
+        assert_eq!(stack.pop(), Some(4));
+        assert_eq!(stack.pop(), Some(1));
+        assert_eq!(stack.pop(), None);
+        assert!(stack.is_empty());
+
+        for elem in 0..100 {
+            stack.push(elem);
+        }
+        assert_eq!(stack.len(), 100);
+        stack.clear();
+        assert!(stack.is_empty());
+        assert_eq!(peek_stack(&stack), None);
+        assert_eq!(stack.pop(), None);
+
+        // Still usable after a clear
+        stack.push(5);
+        assert_eq!(stack.pop(), Some(5));
+    }
+
+    fn check_queue<Q: Queue<i32>>(mut queue: Q) {
+        assert!(queue.is_empty());
+        assert_eq!(queue.len(), 0);
+        assert_eq!(queue.pop(), None);
+        assert_eq!(peek_queue(&queue), None);
+
+        queue.push(1);
+        queue.push(2);
+        queue.push(3);
+        assert!(!queue.is_empty());
+        assert_eq!(queue.len(), 3);
+        assert_eq!(peek_queue(&queue), Some(1));
+
+        assert_eq!(queue.pop(), Some(1));
+        assert_eq!(queue.pop(), Some(2));
+        queue.push(4);
+        assert_eq!(queue.len(), 2);
+        assert_eq!(peek_queue(&queue), Some(3));
+        assert_eq!(queue.pop(), Some(3));
+        assert_eq!(queue.pop(), Some(4));
+        assert_eq!(queue.pop(), None);
+        assert!(queue.is_empty());
+
+        for elem in 0..100 {
+            queue.push(elem);
+        }
+        assert_eq!(queue.len(), 100);
+        queue.clear();
+        assert!(queue.is_empty());
+        assert_eq!(peek_queue(&queue), None);
+        assert_eq!(queue.pop(), None);
+
+        queue.push(5);
+        assert_eq!(queue.pop(), Some(5));
+    }
+
+    fn check_deque<D: Deque<i32>>(mut deque: D) {
+        assert!(deque.is_empty());
+        assert_eq!(deque.pop_front(), None);
+        assert_eq!(deque.pop_back(), None);
+        assert_eq!(peek_front(&deque), None);
+        assert_eq!(peek_back(&deque), None);
+
+        deque.push_front(2);
+        deque.push_back(3);
+        deque.push_front(1);
+        deque.push_back(4);
+        assert_eq!(deque.len(), 4);
+        assert_eq!(peek_front(&deque), Some(1));
+        assert_eq!(peek_back(&deque), Some(4));
+
+        assert_eq!(deque.pop_front(), Some(1));
+        assert_eq!(deque.pop_back(), Some(4));
+        assert_eq!(deque.pop_back(), Some(3));
+        assert_eq!(peek_front(&deque), Some(2));
+        assert_eq!(peek_back(&deque), Some(2));
+        assert_eq!(deque.pop_front(), Some(2));
+        assert_eq!(deque.pop_front(), None);
+        assert!(deque.is_empty());
+
+        for elem in 0..100 {
+            deque.push_back(elem);
+        }
+        deque.clear();
+        assert!(deque.is_empty());
+        assert_eq!(deque.pop_back(), None);
+
+        deque.push_back(5);
+        assert_eq!(deque.pop_front(), Some(5));
+    }
+
+    #[test]
+    fn stacks() {
+        check_stack(first::List::new());
+        check_stack(second::List::new());
+        check_stack(third::List::new());
+        check_stack(third::sync::List::new());
+        check_stack(fourth::List::new());
+        check_stack(sixth::LinkedList::new());
+        check_stack(slab_list::List::new());
+        check_stack(unrolled::List::<_, 4>::new());
+    }
+
+    #[test]
+    fn queues() {
+        check_queue(fourth::List::new());
+        check_queue(fifth::List::new());
+        check_queue(third::queue::PersistentQueue::new());
+        check_queue(sixth::LinkedList::new());
+        check_queue(slab_list::List::new());
+        check_queue(unrolled::List::<_, 4>::new());
+    }
+
+    #[test]
+    fn deques() {
+        check_deque(fourth::List::new());
+        check_deque(sixth::LinkedList::new());
+        check_deque(slab_list::List::new());
+        check_deque(unrolled::List::<_, 4>::new());
+    }
+}
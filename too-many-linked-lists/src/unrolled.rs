@@ -0,0 +1,931 @@
+use alloc::boxed::Box;
+use alloc::format;
+use core::cmp::Ordering;
+use core::fmt::{self, Debug};
+use core::hash::{Hash, Hasher};
+use core::marker::PhantomData;
+use core::mem::MaybeUninit;
+use core::ptr::{self, NonNull};
+
+use crate::traits::{Collection, Deque, Queue, Stack};
+use crate::viz::{self, Graph, ToDot};
+
+type Link<T, const N: usize> = Option<NonNull<Node<T, N>>>;
+
+// A doubly-linked list of blocks, each holding up to `N` elements packed at
+// the start of an inline array. Blocks only ever become empty by popping or
+// removing, at which point they're freed, so every block in the list holds at
+// least one element.
+pub struct List<T, const N: usize> {
+    front: Link<T, N>,
+    back: Link<T, N>,
+    len: usize,
+    _boo: PhantomData<T>,
+}
+
+struct Node<T, const N: usize> {
+    front: Link<T, N>,
+    back: Link<T, N>,
+    len: usize,
+    elems: [MaybeUninit<T>; N],
+}
+
+pub struct Iter<'a, T, const N: usize> {
+    front: Link<T, N>,
+    front_idx: usize,
+    back: Link<T, N>,
+    back_idx: usize,
+    len: usize,
+    _boo: PhantomData<&'a T>,
+}
+
+pub struct IterMut<'a, T, const N: usize> {
+    front: Link<T, N>,
+    front_idx: usize,
+    back: Link<T, N>,
+    back_idx: usize,
+    len: usize,
+    _boo: PhantomData<&'a mut T>,
+}
+
+pub struct IntoIter<T, const N: usize> {
+    list: List<T, N>,
+}
+
+impl<T, const N: usize> Node<T, N> {
+    fn new() -> NonNull<Self> {
+        unsafe {
+            NonNull::new_unchecked(Box::into_raw(Box::new(Node {
+                front: None,
+                back: None,
+                len: 0,
+                elems: [const { MaybeUninit::uninit() }; N],
+            })))
+        }
+    }
+
+    fn is_full(&self) -> bool {
+        self.len == N
+    }
+
+    fn insert(&mut self, idx: usize, elem: T) {
+        debug_assert!(idx <= self.len && self.len < N);
+        unsafe {
+            let base = self.elems.as_mut_ptr();
+            ptr::copy(base.add(idx), base.add(idx + 1), self.len - idx);
+        }
+        self.elems[idx].write(elem);
+        self.len += 1;
+    }
+
+    fn remove(&mut self, idx: usize) -> T {
+        debug_assert!(idx < self.len);
+        unsafe {
+            let elem = self.elems[idx].assume_init_read();
+            let base = self.elems.as_mut_ptr();
+            ptr::copy(base.add(idx + 1), base.add(idx), self.len - idx - 1);
+            self.len -= 1;
+            elem
+        }
+    }
+
+    // Moves the elements from `at` onwards into the empty node `other`.
+    fn split_into(&mut self, at: usize, other: &mut Self) {
+        debug_assert!(at <= self.len && other.len == 0);
+        unsafe {
+            ptr::copy_nonoverlapping(
+                self.elems.as_ptr().add(at),
+                other.elems.as_mut_ptr(),
+                self.len - at,
+            );
+        }
+        other.len = self.len - at;
+        self.len = at;
+    }
+
+    // Moves every element of `other` onto the end of `self`.
+    fn append(&mut self, other: &mut Self) {
+        debug_assert!(self.len + other.len <= N);
+        unsafe {
+            ptr::copy_nonoverlapping(
+                other.elems.as_ptr(),
+                self.elems.as_mut_ptr().add(self.len),
+                other.len,
+            );
+        }
+        self.len += other.len;
+        other.len = 0;
+    }
+}
+
+impl<T, const N: usize> Drop for Node<T, N> {
+    fn drop(&mut self) {
+        unsafe {
+            let elems = ptr::slice_from_raw_parts_mut(self.elems.as_mut_ptr() as *mut T, self.len);
+            ptr::drop_in_place(elems);
+        }
+    }
+}
+
+impl<T, const N: usize> List<T, N> {
+    pub fn new() -> Self {
+        const { assert!(N >= 2, "blocks must hold at least two elements") };
+        List {
+            front: None,
+            back: None,
+            len: 0,
+            _boo: PhantomData,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn clear(&mut self) {
+        while let Some(node) = self.front {
+            self.unlink(node);
+        }
+        self.len = 0;
+    }
+
+    pub fn push_front(&mut self, elem: T) {
+        unsafe {
+            let node = match self.front {
+                Some(node) if !(*node.as_ptr()).is_full() => node,
+                _ => self.link_after(None),
+            };
+            (*node.as_ptr()).insert(0, elem);
+            self.len += 1;
+        }
+    }
+
+    pub fn push_back(&mut self, elem: T) {
+        unsafe {
+            let node = match self.back {
+                Some(node) if !(*node.as_ptr()).is_full() => node,
+                back => self.link_after(back),
+            };
+            let node = &mut *node.as_ptr();
+            node.insert(node.len, elem);
+            self.len += 1;
+        }
+    }
+
+    pub fn pop_front(&mut self) -> Option<T> {
+        self.front.map(|node| unsafe {
+            let elem = (*node.as_ptr()).remove(0);
+            if (*node.as_ptr()).len == 0 {
+                self.unlink(node);
+            }
+            self.len -= 1;
+            elem
+        })
+    }
+
+    pub fn pop_back(&mut self) -> Option<T> {
+        self.back.map(|node| unsafe {
+            let node_len = (*node.as_ptr()).len;
+            let elem = (*node.as_ptr()).remove(node_len - 1);
+            if node_len == 1 {
+                self.unlink(node);
+            }
+            self.len -= 1;
+            elem
+        })
+    }
+
+    pub fn front(&self) -> Option<&T> {
+        self.get(0)
+    }
+
+    pub fn front_mut(&mut self) -> Option<&mut T> {
+        self.get_mut(0)
+    }
+
+    pub fn back(&self) -> Option<&T> {
+        self.len.checked_sub(1).and_then(|index| self.get(index))
+    }
+
+    pub fn back_mut(&mut self) -> Option<&mut T> {
+        self.len
+            .checked_sub(1)
+            .and_then(|index| self.get_mut(index))
+    }
+
+    pub fn get(&self, index: usize) -> Option<&T> {
+        if index >= self.len {
+            return None;
+        }
+        let (node, idx) = self.locate(index);
+        unsafe { Some((*node.as_ptr()).elems[idx].assume_init_ref()) }
+    }
+
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+        if index >= self.len {
+            return None;
+        }
+        let (node, idx) = self.locate(index);
+        unsafe { Some((*node.as_ptr()).elems[idx].assume_init_mut()) }
+    }
+
+    // A full block is split in half first, so this only ever shifts within a
+    // single block.
+    pub fn insert(&mut self, index: usize, elem: T) {
+        assert!(index <= self.len, "insertion index out of bounds");
+        if index == self.len {
+            self.push_back(elem);
+            return;
+        }
+
+        let (mut node, mut idx) = self.locate(index);
+        unsafe {
+            if (*node.as_ptr()).is_full() {
+                let half = self.link_after(Some(node));
+                (*node.as_ptr()).split_into(N / 2, &mut *half.as_ptr());
+                if idx > N / 2 {
+                    node = half;
+                    idx -= N / 2;
+                }
+            }
+            (*node.as_ptr()).insert(idx, elem);
+        }
+        self.len += 1;
+    }
+
+    // A block left less than half full is merged into a neighbour when the
+    // two fit in one block, which keeps the list from degrading into one
+    // element per node.
+    pub fn remove(&mut self, index: usize) -> Option<T> {
+        if index >= self.len {
+            return None;
+        }
+
+        let (node, idx) = self.locate(index);
+        unsafe {
+            let elem = (*node.as_ptr()).remove(idx);
+            self.len -= 1;
+
+            let node_len = (*node.as_ptr()).len;
+            if node_len == 0 {
+                self.unlink(node);
+            } else if node_len < N / 2 {
+                let next = (*node.as_ptr()).back;
+                let prev = (*node.as_ptr()).front;
+                if let Some(next) = next.filter(|next| node_len + (*next.as_ptr()).len <= N) {
+                    (*node.as_ptr()).append(&mut *next.as_ptr());
+                    self.unlink(next);
+                } else if let Some(prev) = prev.filter(|prev| node_len + (*prev.as_ptr()).len <= N)
+                {
+                    (*prev.as_ptr()).append(&mut *node.as_ptr());
+                    self.unlink(node);
+                }
+            }
+            Some(elem)
+        }
+    }
+
+    pub fn iter(&self) -> Iter<'_, T, N> {
+        Iter {
+            front: self.front,
+            front_idx: 0,
+            back: self.back,
+            back_idx: self.back.map_or(0, |node| unsafe { (*node.as_ptr()).len }),
+            len: self.len,
+            _boo: PhantomData,
+        }
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_, T, N> {
+        IterMut {
+            front: self.front,
+            front_idx: 0,
+            back: self.back,
+            back_idx: self.back.map_or(0, |node| unsafe { (*node.as_ptr()).len }),
+            len: self.len,
+            _boo: PhantomData,
+        }
+    }
+
+    // Finds the block holding `index` and the position inside it, walking
+    // from whichever end is closer.
+    fn locate(&self, index: usize) -> (NonNull<Node<T, N>>, usize) {
+        debug_assert!(index < self.len);
+        unsafe {
+            if index < self.len / 2 {
+                let mut node = self.front.unwrap();
+                let mut idx = index;
+                while idx >= (*node.as_ptr()).len {
+                    idx -= (*node.as_ptr()).len;
+                    node = (*node.as_ptr()).back.unwrap();
+                }
+                (node, idx)
+            } else {
+                let mut node = self.back.unwrap();
+                let mut from_back = self.len - 1 - index;
+                while from_back >= (*node.as_ptr()).len {
+                    from_back -= (*node.as_ptr()).len;
+                    node = (*node.as_ptr()).front.unwrap();
+                }
+                (node, (*node.as_ptr()).len - 1 - from_back)
+            }
+        }
+    }
+
+    // Links a new, empty block right after `prev`, or at the front if `prev`
+    // is `None`.
+    fn link_after(&mut self, prev: Link<T, N>) -> NonNull<Node<T, N>> {
+        let new = Node::new();
+        unsafe {
+            let next = match prev {
+                Some(prev) => (*prev.as_ptr()).back.replace(new),
+                None => self.front.replace(new),
+            };
+            match next {
+                Some(next) => (*next.as_ptr()).front = Some(new),
+                None => self.back = Some(new),
+            }
+            (*new.as_ptr()).front = prev;
+            (*new.as_ptr()).back = next;
+        }
+        new
+    }
+
+    // Unlinks and frees `node`, dropping whatever elements it still holds.
+    fn unlink(&mut self, node: NonNull<Node<T, N>>) {
+        unsafe {
+            let boxed = Box::from_raw(node.as_ptr());
+            match boxed.front {
+                Some(prev) => (*prev.as_ptr()).back = boxed.back,
+                None => self.front = boxed.back,
+            }
+            match boxed.back {
+                Some(next) => (*next.as_ptr()).front = boxed.front,
+                None => self.back = boxed.front,
+            }
+        }
+    }
+}
+
+impl<T, const N: usize> Drop for List<T, N> {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+impl<T, const N: usize> Default for List<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Clone, const N: usize> Clone for List<T, N> {
+    fn clone(&self) -> Self {
+        self.iter().cloned().collect()
+    }
+}
+
+impl<T, const N: usize> Extend<T> for List<T, N> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for elem in iter {
+            self.push_back(elem);
+        }
+    }
+}
+
+impl<T, const N: usize> FromIterator<T> for List<T, N> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut list = Self::new();
+        list.extend(iter);
+        list
+    }
+}
+
+impl<T: Debug, const N: usize> Debug for List<T, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self).finish()
+    }
+}
+
+impl<T: PartialEq, const N: usize> PartialEq for List<T, N> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.iter().eq(other)
+    }
+}
+
+impl<T: Eq, const N: usize> Eq for List<T, N> {}
+
+impl<T: PartialOrd, const N: usize> PartialOrd for List<T, N> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.iter().partial_cmp(other)
+    }
+}
+
+impl<T: Ord, const N: usize> Ord for List<T, N> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.iter().cmp(other)
+    }
+}
+
+impl<T: Hash, const N: usize> Hash for List<T, N> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.len().hash(state);
+        for elem in self {
+            elem.hash(state);
+        }
+    }
+}
+
+impl<'a, T, const N: usize> IntoIterator for &'a List<T, N> {
+    type IntoIter = Iter<'a, T, N>;
+    type Item = &'a T;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, T, const N: usize> IntoIterator for &'a mut List<T, N> {
+    type IntoIter = IterMut<'a, T, N>;
+    type Item = &'a mut T;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+impl<T, const N: usize> IntoIterator for List<T, N> {
+    type IntoIter = IntoIter<T, N>;
+    type Item = T;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter { list: self }
+    }
+}
+
+// The iterators count down `len` rather than comparing positions, so the
+// front and back halves never hand out the same element twice. `back_idx` is
+// one past the next element to yield from the back.
+impl<'a, T, const N: usize> Iterator for Iter<'a, T, N> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.len == 0 {
+            return None;
+        }
+        unsafe {
+            let node = self.front.unwrap().as_ptr();
+            let elem = (*node).elems[self.front_idx].assume_init_ref();
+            self.front_idx += 1;
+            if self.front_idx == (*node).len {
+                self.front = (*node).back;
+                self.front_idx = 0;
+            }
+            self.len -= 1;
+            Some(elem)
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<T, const N: usize> DoubleEndedIterator for Iter<'_, T, N> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.len == 0 {
+            return None;
+        }
+        unsafe {
+            let mut node = self.back.unwrap().as_ptr();
+            if self.back_idx == 0 {
+                node = (*node).front.unwrap().as_ptr();
+                self.back = NonNull::new(node);
+                self.back_idx = (*node).len;
+            }
+            self.back_idx -= 1;
+            self.len -= 1;
+            Some((*node).elems[self.back_idx].assume_init_ref())
+        }
+    }
+}
+
+impl<T, const N: usize> ExactSizeIterator for Iter<'_, T, N> {
+    fn len(&self) -> usize {
+        self.len
+    }
+}
+
+impl<'a, T, const N: usize> Iterator for IterMut<'a, T, N> {
+    type Item = &'a mut T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.len == 0 {
+            return None;
+        }
+        unsafe {
+            let node = self.front.unwrap().as_ptr();
+            let elem = (*node).elems[self.front_idx].assume_init_mut();
+            self.front_idx += 1;
+            if self.front_idx == (*node).len {
+                self.front = (*node).back;
+                self.front_idx = 0;
+            }
+            self.len -= 1;
+            Some(elem)
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<T, const N: usize> DoubleEndedIterator for IterMut<'_, T, N> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.len == 0 {
+            return None;
+        }
+        unsafe {
+            let mut node = self.back.unwrap().as_ptr();
+            if self.back_idx == 0 {
+                node = (*node).front.unwrap().as_ptr();
+                self.back = NonNull::new(node);
+                self.back_idx = (*node).len;
+            }
+            self.back_idx -= 1;
+            self.len -= 1;
+            Some((*node).elems[self.back_idx].assume_init_mut())
+        }
+    }
+}
+
+impl<T, const N: usize> ExactSizeIterator for IterMut<'_, T, N> {
+    fn len(&self) -> usize {
+        self.len
+    }
+}
+
+impl<T, const N: usize> Iterator for IntoIter<T, N> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.list.pop_front()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.list.len, Some(self.list.len))
+    }
+}
+
+impl<T, const N: usize> DoubleEndedIterator for IntoIter<T, N> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.list.pop_back()
+    }
+}
+
+impl<T, const N: usize> ExactSizeIterator for IntoIter<T, N> {
+    fn len(&self) -> usize {
+        self.list.len
+    }
+}
+
+unsafe impl<T: Send, const N: usize> Send for List<T, N> {}
+unsafe impl<T: Sync, const N: usize> Sync for List<T, N> {}
+
+unsafe impl<T: Sync, const N: usize> Send for Iter<'_, T, N> {}
+unsafe impl<T: Sync, const N: usize> Sync for Iter<'_, T, N> {}
+
+unsafe impl<T: Send, const N: usize> Send for IterMut<'_, T, N> {}
+unsafe impl<T: Sync, const N: usize> Sync for IterMut<'_, T, N> {}
+
+impl<T, const N: usize> Collection<T> for List<T, N> {
+    type Ref<'a>
+        = &'a T
+    where
+        Self: 'a;
+
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn clear(&mut self) {
+        List::clear(self)
+    }
+}
+
+impl<T, const N: usize> Stack<T> for List<T, N> {
+    fn push(&mut self, elem: T) {
+        self.push_front(elem)
+    }
+
+    fn pop(&mut self) -> Option<T> {
+        self.pop_front()
+    }
+
+    fn peek(&self) -> Option<&T> {
+        self.front()
+    }
+}
+
+impl<T, const N: usize> Queue<T> for List<T, N> {
+    fn push(&mut self, elem: T) {
+        self.push_back(elem)
+    }
+
+    fn pop(&mut self) -> Option<T> {
+        self.pop_front()
+    }
+
+    fn peek(&self) -> Option<&T> {
+        self.front()
+    }
+}
+
+impl<T, const N: usize> Deque<T> for List<T, N> {
+    fn push_front(&mut self, elem: T) {
+        List::push_front(self, elem)
+    }
+
+    fn push_back(&mut self, elem: T) {
+        List::push_back(self, elem)
+    }
+
+    fn pop_front(&mut self) -> Option<T> {
+        List::pop_front(self)
+    }
+
+    fn pop_back(&mut self) -> Option<T> {
+        List::pop_back(self)
+    }
+
+    fn peek_front(&self) -> Option<&T> {
+        self.front()
+    }
+
+    fn peek_back(&self) -> Option<&T> {
+        self.back()
+    }
+}
+
+// One DOT node per block, labelled with the elements it holds.
+impl<T: Debug, const N: usize> ToDot for List<T, N> {
+    fn write_dot(&self, name: &str, graph: &mut Graph) {
+        let root = graph.root(name);
+        if let Some(back) = self.back {
+            graph.edge(&root, &viz::id(back.as_ptr()), "label=\"back\"");
+        }
+        let mut from = root;
+        let mut attrs = "label=\"front\"";
+        let mut cur = self.front;
+        while let Some(node) = cur {
+            let ptr = node.as_ptr();
+            let node = unsafe { node.as_ref() };
+            let elems =
+                unsafe { core::slice::from_raw_parts(node.elems.as_ptr() as *const T, node.len) };
+            graph.node(ptr, &format!("{:?}", elems));
+            graph.edge(&from, &viz::id(ptr), attrs);
+            if let Some(prev) = node.front {
+                graph.edge(&viz::id(ptr), &viz::id(prev.as_ptr()), "style=dashed");
+            }
+            from = viz::id(ptr);
+            attrs = "";
+            cur = node.back;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::List;
+    use crate::test_util::XorShift;
+    use std::collections::VecDeque;
+    use std::rc::Rc;
+
+    // Checks the block links agree in both directions, no block is empty and
+    // the lengths add up.
+    fn check_blocks<T, const N: usize>(list: &List<T, N>) {
+        let mut total = 0;
+        let mut prev = None;
+        let mut cur = list.front;
+        unsafe {
+            while let Some(node) = cur {
+                assert_eq!((*node.as_ptr()).front, prev);
+                let len = (*node.as_ptr()).len;
+                assert!(len > 0 && len <= N);
+                total += len;
+                prev = cur;
+                cur = (*node.as_ptr()).back;
+            }
+        }
+        assert_eq!(list.back, prev);
+        assert_eq!(total, list.len());
+    }
+
+    fn blocks<T, const N: usize>(list: &List<T, N>) -> usize {
+        let mut count = 0;
+        let mut cur = list.front;
+        while let Some(node) = cur {
+            count += 1;
+            cur = unsafe { (*node.as_ptr()).back };
+        }
+        count
+    }
+
+    #[test]
+    fn push_pop_both_ends() {
+        let mut list = List::<i32, 4>::new();
+        assert_eq!(list.pop_front(), None);
+        assert_eq!(list.pop_back(), None);
+        assert_eq!(list.front(), None);
+        assert_eq!(list.back(), None);
+
+        for i in 0..10 {
+            list.push_back(i);
+            list.push_front(-i - 1);
+        }
+        check_blocks(&list);
+        assert_eq!(list.len(), 20);
+        assert_eq!(list.front(), Some(&-10));
+        assert_eq!(list.back(), Some(&9));
+
+        *list.front_mut().unwrap() = 100;
+        *list.back_mut().unwrap() = 200;
+        assert_eq!(list.pop_front(), Some(100));
+        assert_eq!(list.pop_back(), Some(200));
+
+        let expected: Vec<i32> = (-9..9).collect();
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), expected);
+
+        while list.pop_back().is_some() {
+            check_blocks(&list);
+        }
+        assert!(list.is_empty());
+        assert_eq!(blocks(&list), 0);
+    }
+
+    #[test]
+    fn packs_elements_into_blocks() {
+        let list: List<i32, 8> = (0..64).collect();
+        assert_eq!(blocks(&list), 8);
+
+        let mut list = List::<i32, 8>::new();
+        for i in 0..64 {
+            list.push_front(i);
+        }
+        assert_eq!(blocks(&list), 8);
+    }
+
+    #[test]
+    fn insert_splits_blocks() {
+        let mut list: List<i32, 4> = (0..8).collect();
+        assert_eq!(blocks(&list), 2);
+
+        list.insert(1, 100);
+        check_blocks(&list);
+        assert_eq!(blocks(&list), 3);
+        list.insert(0, 200);
+        list.insert(10, 300);
+        list.insert(5, 400);
+        check_blocks(&list);
+        assert_eq!(
+            list.iter().copied().collect::<Vec<_>>(),
+            vec![200, 0, 100, 1, 2, 400, 3, 4, 5, 6, 7, 300]
+        );
+        assert_eq!(list.get(5), Some(&400));
+        assert_eq!(list.get(12), None);
+        *list.get_mut(11).unwrap() += 1;
+        assert_eq!(list.back(), Some(&301));
+    }
+
+    #[test]
+    #[should_panic]
+    fn insert_out_of_bounds() {
+        let mut list: List<i32, 4> = (0..3).collect();
+        list.insert(4, 0);
+    }
+
+    #[test]
+    fn remove_merges_blocks() {
+        let mut list: List<i32, 4> = (0..16).collect();
+        assert_eq!(blocks(&list), 4);
+        assert_eq!(list.remove(16), None);
+
+        assert_eq!(list.remove(1), Some(1));
+        assert_eq!(list.remove(1), Some(2));
+        assert_eq!(list.remove(1), Some(3));
+        check_blocks(&list);
+        // [0] is under half full, but [4, 5, 6, 7] has no room for it
+        assert_eq!(blocks(&list), 4);
+
+        assert_eq!(list.remove(1), Some(4));
+        assert_eq!(list.remove(1), Some(5));
+        assert_eq!(list.remove(1), Some(6));
+        check_blocks(&list);
+        // [7] can't go into [8, 9, 10, 11] either, so it joins [0] instead
+        assert_eq!(blocks(&list), 3);
+
+        assert_eq!(list.remove(0), Some(0));
+        assert_eq!(list.remove(0), Some(7));
+        check_blocks(&list);
+        assert_eq!(blocks(&list), 2);
+        assert_eq!(
+            list.iter().copied().collect::<Vec<_>>(),
+            (8..16).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn double_ended_iteration() {
+        let mut list: List<i32, 3> = (0..10).collect();
+
+        let mut iter = list.iter();
+        assert_eq!(iter.len(), 10);
+        assert_eq!(iter.next(), Some(&0));
+        assert_eq!(iter.next_back(), Some(&9));
+        assert_eq!(iter.next_back(), Some(&8));
+        assert_eq!(iter.next_back(), Some(&7));
+        assert_eq!(iter.next_back(), Some(&6));
+        assert_eq!(iter.len(), 5);
+        assert_eq!(iter.collect::<Vec<_>>(), vec![&1, &2, &3, &4, &5]);
+        assert_eq!(list.iter().rev().count(), 10);
+
+        for elem in list.iter_mut().rev().take(3) {
+            *elem *= 10;
+        }
+        let mut iter = list.iter_mut();
+        assert_eq!(iter.next_back(), Some(&mut 90));
+        assert_eq!(iter.next(), Some(&mut 0));
+
+        let mut into_iter = list.into_iter();
+        assert_eq!(into_iter.next_back(), Some(90));
+        assert_eq!(into_iter.next(), Some(0));
+        assert_eq!(into_iter.len(), 8);
+    }
+
+    #[test]
+    fn std_traits() {
+        let mut list: List<i32, 4> = (0..10).collect();
+        assert_eq!(format!("{:?}", list), "[0, 1, 2, 3, 4, 5, 6, 7, 8, 9]");
+
+        let copy = list.clone();
+        assert_eq!(copy, list);
+        list.push_back(10);
+        assert_ne!(copy, list);
+        assert!(copy < list);
+        assert_eq!(List::<i32, 4>::default(), List::new());
+    }
+
+    #[test]
+    fn drops_every_element() {
+        let counter = Rc::new(());
+        {
+            let mut list: List<Rc<()>, 4> = (0..10).map(|_| counter.clone()).collect();
+            list.remove(3);
+            list.insert(2, counter.clone());
+            list.pop_front();
+            assert_eq!(Rc::strong_count(&counter), 10);
+        }
+        assert_eq!(Rc::strong_count(&counter), 1);
+    }
+
+    #[test]
+    fn matches_vec_deque() {
+        let iterations = if cfg!(miri) { 500 } else { 20_000 };
+        let mut rng = XorShift(0x2545_F491_4F6C_DD1D);
+        let mut list = List::<usize, 5>::new();
+        let mut model = VecDeque::new();
+
+        for i in 0..iterations {
+            match rng.next(8) {
+                0 => {
+                    list.push_front(i);
+                    model.push_front(i);
+                }
+                1 => {
+                    list.push_back(i);
+                    model.push_back(i);
+                }
+                2 => assert_eq!(list.pop_front(), model.pop_front()),
+                3 => assert_eq!(list.pop_back(), model.pop_back()),
+                4 | 5 => {
+                    let index = rng.next(model.len() + 1);
+                    list.insert(index, i);
+                    model.insert(index, i);
+                }
+                _ => {
+                    let index = rng.next(model.len() + 1);
+                    assert_eq!(list.remove(index), model.remove(index));
+                }
+            }
+
+            check_blocks(&list);
+            assert!(list.iter().eq(model.iter()));
+            assert!(list.iter().rev().eq(model.iter().rev()));
+        }
+    }
+}
@@ -0,0 +1,195 @@
+use alloc::collections::BTreeSet;
+use alloc::format;
+use alloc::string::String;
+use core::fmt::{Debug, Write};
+
+// Lists describe themselves to a `Graph`: one DOT node per allocation, keyed
+// by its address, plus a plaintext node per list for its head (and tail)
+// pointers. Nodes already in the graph are skipped, so several lists that
+// share structure come out as a single DAG.
+pub trait ToDot {
+    fn write_dot(&self, name: &str, graph: &mut Graph);
+
+    fn to_dot(&self) -> String {
+        let mut graph = Graph::new();
+        self.write_dot("list", &mut graph);
+        graph.finish()
+    }
+}
+
+pub fn to_dot_all<L: ToDot>(lists: &[(&str, &L)]) -> String {
+    let mut graph = Graph::new();
+    for (name, list) in lists {
+        list.write_dot(name, &mut graph);
+    }
+    graph.finish()
+}
+
+pub struct Graph {
+    out: String,
+    seen: BTreeSet<*const ()>,
+}
+
+impl Graph {
+    fn new() -> Self {
+        Graph {
+            out: String::from("digraph {\n    rankdir=LR;\n    node [shape=box];\n"),
+            seen: BTreeSet::new(),
+        }
+    }
+
+    fn finish(mut self) -> String {
+        self.out.push_str("}\n");
+        self.out
+    }
+
+    pub(crate) fn root(&mut self, name: &str) -> String {
+        let id = quote(name);
+        let _ = writeln!(self.out, "    {} [shape=plaintext];", id);
+        id
+    }
+
+    // Adds the node behind `ptr` unless it's already there. Returns whether
+    // it was new, so callers can stop walking once they hit shared nodes.
+    pub(crate) fn node<P>(&mut self, ptr: *const P, label: &str) -> bool {
+        if !self.seen.insert(ptr as *const ()) {
+            return false;
+        }
+        let _ = writeln!(self.out, "    {} [label={}];", id(ptr), quote(label));
+        true
+    }
+
+    pub(crate) fn edge(&mut self, from: &str, to: &str, attrs: &str) {
+        if attrs.is_empty() {
+            let _ = writeln!(self.out, "    {} -> {};", from, to);
+        } else {
+            let _ = writeln!(self.out, "    {} -> {} [{}];", from, to, attrs);
+        }
+    }
+}
+
+pub(crate) fn id<P>(ptr: *const P) -> String {
+    format!("\"{:p}\"", ptr)
+}
+
+pub(crate) fn rc_label<T: Debug>(elem: &T, strong: usize, weak: usize) -> String {
+    format!("{:?}\nstrong: {}\nweak: {}", elem, strong, weak)
+}
+
+fn quote(s: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{to_dot_all, ToDot};
+    use crate::{fifth, first, fourth, second, sixth, third, unrolled};
+
+    fn count(haystack: &str, needle: &str) -> usize {
+        haystack.matches(needle).count()
+    }
+
+    fn nodes(dot: &str) -> usize {
+        dot.lines()
+            .filter(|line| line.contains("[label=") && !line.contains(" -> "))
+            .count()
+    }
+
+    fn edges(dot: &str) -> usize {
+        count(dot, " -> ")
+    }
+
+    #[test]
+    fn box_lists() {
+        let mut list = first::List::new();
+        list.push(1);
+        list.push(2);
+        let dot = list.to_dot();
+        assert!(dot.starts_with("digraph {\n"));
+        assert!(dot.ends_with("}\n"));
+        assert_eq!(nodes(&dot), 2);
+        assert_eq!(edges(&dot), 2);
+
+        let list: second::List<&str> = vec!["a", "\"b\""].into_iter().collect();
+        let dot = list.to_dot();
+        assert_eq!(nodes(&dot), 2);
+        assert!(dot.contains(r#"[label="\"\\\"b\\\"\""]"#));
+    }
+
+    #[test]
+    fn third_sharing() {
+        let shared = third::List::new().prepend(1).prepend(2);
+        let a = shared.prepend(3);
+        let b = shared.prepend(4);
+
+        let dot = a.to_dot();
+        assert_eq!(nodes(&dot), 3);
+        assert!(dot.contains(r#"[label="2\nstrong: 3\nweak: 0"]"#));
+
+        // Two heads, one shared tail: 2 and 1 show up once each
+        let dot = to_dot_all(&[("a", &a), ("b", &b), ("shared", &shared)]);
+        assert_eq!(nodes(&dot), 4);
+        assert_eq!(count(&dot, "[shape=plaintext]"), 3);
+        // 3 head pointers, 3 -> 2, 4 -> 2 and 2 -> 1
+        assert_eq!(edges(&dot), 6);
+    }
+
+    #[test]
+    fn third_sync_counts() {
+        let base = third::sync::List::new().prepend(1);
+        let _other = base.prepend(2);
+        let dot = base.to_dot();
+        assert!(dot.contains(r#"[label="1\nstrong: 2\nweak: 0"]"#));
+    }
+
+    #[test]
+    fn doubly_linked_lists() {
+        let list: fourth::List<i32> = (1..=3).collect();
+        let dot = list.to_dot();
+        assert_eq!(nodes(&dot), 3);
+        // head, tail, two next and two prev links
+        assert_eq!(edges(&dot), 6);
+        assert_eq!(count(&dot, "style=dashed"), 2);
+        // The middle node is owned by both of its neighbours
+        assert!(dot.contains(r#"[label="2\nstrong: 2\nweak: 0"]"#));
+
+        let list: sixth::LinkedList<i32> = (1..=3).collect();
+        let dot = list.to_dot();
+        assert_eq!(nodes(&dot), 3);
+        assert_eq!(edges(&dot), 6);
+
+        let mut list = fifth::List::new();
+        list.push(1);
+        list.push(2);
+        let dot = list.to_dot();
+        assert_eq!(nodes(&dot), 2);
+        assert_eq!(edges(&dot), 3);
+    }
+
+    #[test]
+    fn unrolled_blocks() {
+        let list: unrolled::List<i32, 4> = (0..6).collect();
+        let dot = list.to_dot();
+        assert_eq!(nodes(&dot), 2);
+        assert!(dot.contains(r#"[label="[0, 1, 2, 3]"]"#));
+        assert!(dot.contains(r#"[label="[4, 5]"]"#));
+    }
+
+    #[test]
+    fn empty_list() {
+        let dot = second::List::<i32>::new().to_dot();
+        assert_eq!(nodes(&dot), 0);
+        assert_eq!(edges(&dot), 0);
+        assert_eq!(count(&dot, "[shape=plaintext]"), 1);
+    }
+}
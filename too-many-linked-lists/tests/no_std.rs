@@ -0,0 +1,48 @@
+// Integration tests link against the library as it's normally built, not
+// the `cfg(test)` one, so with `cargo test --no-default-features` this runs
+// the lists out of the real `core` + `alloc` build.
+#![cfg(not(feature = "std"))]
+
+use too_many_linked_lists::codec::{self, Le};
+use too_many_linked_lists::pool::NodePool;
+use too_many_linked_lists::skiplist::SkipMap;
+use too_many_linked_lists::stream::Stream;
+use too_many_linked_lists::viz::ToDot;
+use too_many_linked_lists::{fourth, second, sixth, third, unrolled};
+
+#[test]
+fn lists_work_without_std() {
+    let mut stack: second::List<_> = (1..=3).collect();
+    stack.push(0);
+    stack.sort();
+    assert_eq!(stack.iter().copied().collect::<Vec<_>>(), [0, 1, 2, 3]);
+
+    let shared = third::List::new().prepend(1);
+    let list = shared.prepend(2);
+    assert_eq!(list.tail(), shared);
+    assert!(list.to_dot().contains("strong: 2"));
+    let decoded = codec::decode::<i32, _>(&codec::encode(&[&list, &shared], &Le), &Le).unwrap();
+    assert!(decoded[0].tail().ptr_eq(&decoded[1]));
+
+    let mut deque: fourth::List<_> = (1..=3).collect();
+    assert_eq!(deque.pop_front(), Some(1));
+    assert_eq!(deque.pop_back(), Some(3));
+
+    let blocks: unrolled::List<_, 4> = (0..10).collect();
+    assert_eq!(blocks.get(7), Some(&7));
+
+    let map: SkipMap<_, _> = [(2, "b"), (1, "a")].into_iter().collect();
+    assert_eq!(map.first(), Some((&1, &"a")));
+
+    let evens = Stream::iterate(0, |n| n + 2);
+    assert_eq!(evens.iter().nth(5), Some(&10));
+}
+
+#[test]
+fn pool_works_without_std() {
+    let pool = NodePool::new();
+    let mut list = sixth::LinkedList::new_in(&pool);
+    list.extend(0..100);
+    list.clear();
+    assert_eq!(pool.free_blocks(), 100);
+}